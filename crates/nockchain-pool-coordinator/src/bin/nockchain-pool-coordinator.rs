@@ -69,9 +69,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         pool_name: args.pool_name.clone(),
         fee_percent: args.pool_fee,
         min_payout: args.min_payout,
+        block_reward: 50_000_000,
         payout_interval: 3600,
         share_window_hours: 24,
         validation_threshold: 0.95,
+        proof_verification_threads: std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1),
+        proof_verification_pin_cores: true,
+        payout_scheme: nockchain_pool_coordinator::payout::PayoutScheme::Proportional,
+        reward_recipients: Vec::new(),
+        vardiff_target_shares_per_minute: 15.0,
+        vardiff_variance_percent: 50.0,
+        vardiff_min_difficulty: 1,
+        vardiff_max_difficulty: 1_000_000,
+        payout_confirmation_depth: 6,
+        idle_worker_timeout_secs: 600,
     };
     
     // Initialize pool coordinator
@@ -92,18 +105,54 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
     });
-    
+
+    // Start Redis health-check task
+    let coordinator_clone = coordinator.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(15));
+        loop {
+            interval.tick().await;
+            if let Err(e) = coordinator_clone.check_redis_health().await {
+                error!("Redis health check error: {}", e);
+            }
+        }
+    });
+
     // Create Stratum server
     let stratum_server = StratumServer::new(coordinator.clone()).await;
-    
+
+    // Start idle-worker watchdog task
+    let watchdog_server = stratum_server.clone();
+    let idle_timeout_secs = coordinator.config().idle_worker_timeout_secs;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            let disconnected = watchdog_server.disconnect_idle_workers(idle_timeout_secs).await;
+            if disconnected > 0 {
+                info!("Idle watchdog disconnected {} worker(s)", disconnected);
+            }
+        }
+    });
+
+    // Start the SV2-style TCP listener
+    let sv2_server = stratum_server.clone();
+    let stratum_bind = args.stratum_bind;
+    tokio::spawn(async move {
+        if let Err(e) = sv2_server.run_sv2_listener(stratum_bind).await {
+            error!("SV2 Stratum listener error: {}", e);
+        }
+    });
+
     // Start HTTP API server
     let api_router = axum::Router::new()
         .merge(stratum_server.router())
+        .merge(nockchain_pool_coordinator::rpc::router(coordinator.clone()))
         .route("/metrics", axum::routing::get(|| async { metrics_handler() }));
-    
+
     let http_server = axum::Server::bind(&args.http_bind)
         .serve(api_router.into_make_service_with_connect_info::<SocketAddr>());
-    
+
     info!("Stratum server listening on {}", args.stratum_bind);
     info!("HTTP API listening on {}", args.http_bind);
     info!("Prometheus metrics on port {}", args.metrics_port);
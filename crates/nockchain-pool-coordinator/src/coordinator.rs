@@ -6,15 +6,29 @@ use tracing::{info, warn, error, debug};
 use serde_json::json;
 
 use crate::database::{RedisStore, MinerRecord, ShareRecord, JobTemplate, PoolStats, MinerReputation};
-use crate::shares::{ShareSubmission, ShareValidator, ShareValidation};
-use crate::payout::PayoutManager;
+use crate::shares::{ShareSubmission, ShareValidator, ShareValidation, ComputationProofConfig};
+use crate::payout::{PayoutManager, PayoutScheme, RewardRecipient};
+use crate::stats::StatsTracker;
+use crate::metrics;
 use crate::error::{PoolError, Result};
 
+/// Hashrate estimation window used by the worker stats subsystem.
+const STATS_HASHRATE_WINDOW_SECS: i64 = 600;
+
 pub struct PoolCoordinator {
     redis: Arc<RwLock<RedisStore>>,
     share_validator: Arc<ShareValidator>,
     payout_manager: Arc<PayoutManager>,
+    stats: Arc<StatsTracker>,
     config: PoolConfig,
+    /// Serializes `trigger_block_payout` end to end. Computing a PPLNS window
+    /// and applying its carried-forward balances is two separate Redis
+    /// round-trips (`calculate_payouts` then `apply_pending_balances`), so
+    /// without this lock two blocks found close together could interleave
+    /// and mark/read the shared `shares:scored`/pending-balance state out of
+    /// order -- exactly the kind of pagination-loop precondition this call
+    /// site now depends on for correctness.
+    payout_trigger_lock: tokio::sync::Mutex<()>,
 }
 
 #[derive(Debug, Clone)]
@@ -22,9 +36,31 @@ pub struct PoolConfig {
     pub pool_name: String,
     pub fee_percent: f64,
     pub min_payout: u64,
+    /// Coin reward paid out per block found, before the pool fee is taken.
+    pub block_reward: u64,
     pub payout_interval: u64,
     pub share_window_hours: u64,
     pub validation_threshold: f64,
+    /// Worker threads used to generate/verify computation proofs in parallel.
+    /// `1` keeps the original serial behavior.
+    pub proof_verification_threads: usize,
+    /// Pin each proof-verification worker thread to a distinct physical core.
+    pub proof_verification_pin_cores: bool,
+    pub payout_scheme: PayoutScheme,
+    /// Recipients of the pool fee (operator, dev fund, infra, ...). Percentages must sum to `fee_percent`.
+    pub reward_recipients: Vec<RewardRecipient>,
+    /// Target shares-per-minute each connection's vardiff controller retargets toward.
+    pub vardiff_target_shares_per_minute: f64,
+    /// How far the observed rate may drift from the target before retargeting, in percent (e.g. 50.0 = 50%).
+    pub vardiff_variance_percent: f64,
+    pub vardiff_min_difficulty: u64,
+    pub vardiff_max_difficulty: u64,
+    /// Confirmations a submitted payout transaction must reach before it's
+    /// considered final.
+    pub payout_confirmation_depth: u32,
+    /// How long an authorized worker may go without an accepted share before
+    /// the idle watchdog disconnects it.
+    pub idle_worker_timeout_secs: i64,
 }
 
 impl Default for PoolConfig {
@@ -33,9 +69,20 @@ impl Default for PoolConfig {
             pool_name: "Nockchain Mining Pool".to_string(),
             fee_percent: 2.0,
             min_payout: 1_000_000,
+            block_reward: 50_000_000,
             payout_interval: 3600,
             share_window_hours: 24,
             validation_threshold: 0.95,
+            proof_verification_threads: 1,
+            proof_verification_pin_cores: false,
+            payout_scheme: PayoutScheme::Proportional,
+            reward_recipients: Vec::new(),
+            vardiff_target_shares_per_minute: 15.0,
+            vardiff_variance_percent: 50.0,
+            vardiff_min_difficulty: 1,
+            vardiff_max_difficulty: 1_000_000,
+            payout_confirmation_depth: 6,
+            idle_worker_timeout_secs: 600,
         }
     }
 }
@@ -43,17 +90,41 @@ impl Default for PoolConfig {
 impl PoolCoordinator {
     pub async fn new(redis_url: &str, config: PoolConfig) -> Result<Self> {
         let redis = Arc::new(RwLock::new(RedisStore::new(redis_url).await?));
-        let share_validator = Arc::new(ShareValidator::new(redis.clone(), config.validation_threshold));
-        let payout_manager = Arc::new(PayoutManager::new(redis.clone(), config.fee_percent));
-        
+        let proof_config = ComputationProofConfig {
+            threads: config.proof_verification_threads,
+            pin_cores: config.proof_verification_pin_cores,
+        };
+        let share_validator = Arc::new(ShareValidator::with_proof_config(
+            redis.clone(),
+            config.validation_threshold,
+            proof_config,
+        ));
+        let payout_manager = Arc::new(PayoutManager::with_recipients(
+            redis.clone(),
+            config.fee_percent,
+            config.payout_scheme.clone(),
+            config.reward_recipients.clone(),
+        )?);
+        let stats = Arc::new(StatsTracker::new(STATS_HASHRATE_WINDOW_SECS));
+
         Ok(Self {
             redis,
             share_validator,
             payout_manager,
+            stats,
             config,
+            payout_trigger_lock: tokio::sync::Mutex::new(()),
         })
     }
-    
+
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> &Arc<StatsTracker> {
+        &self.stats
+    }
+
     pub async fn register_miner(&self, address: &str, worker_name: &str) -> Result<()> {
         let mut redis = self.redis.write().await;
         
@@ -79,11 +150,37 @@ impl PoolCoordinator {
         Ok(())
     }
     
-    pub async fn submit_share(&self, submission: ShareSubmission) -> Result<ShareValidation> {
-        // Validate share
-        let validation = self.share_validator.validate_share(submission.clone()).await?;
-        
+    pub async fn submit_share(&self, submission: ShareSubmission, assigned_difficulty: u64) -> Result<ShareValidation> {
+        metrics::SHARES_SUBMITTED.inc();
+
+        // Validate share. A `Database` error likely means a transient Redis
+        // blip, so reconnect and retry once before surfacing it as a rejection.
+        let validation = match self.share_validator.validate_share(submission.clone(), assigned_difficulty).await {
+            Ok(validation) => validation,
+            Err(PoolError::Database(e)) => {
+                warn!("Redis error validating share ({}), reconnecting and retrying once", e);
+                self.redis.write().await.reconnect().await?;
+
+                match self.share_validator.validate_share(submission.clone(), assigned_difficulty).await {
+                    Ok(validation) => validation,
+                    Err(e) => {
+                        metrics::SHARES_REJECTED.inc();
+                        self.stats.record_rejected(&submission.miner_id, &e).await;
+                        return Err(e);
+                    }
+                }
+            }
+            Err(e) => {
+                metrics::SHARES_REJECTED.inc();
+                self.stats.record_rejected(&submission.miner_id, &e).await;
+                return Err(e);
+            }
+        };
+
         if validation.is_valid {
+            metrics::SHARES_ACCEPTED.inc();
+            self.stats.record_accepted(&submission.miner_id, validation.difficulty).await;
+
             // Save share record
             let share_record = ShareRecord {
                 id: uuid::Uuid::new_v4().to_string(),
@@ -93,6 +190,10 @@ impl PoolCoordinator {
                     crate::shares::ShareType::ComputationProof { nonce, .. } => *nonce,
                     crate::shares::ShareType::ValidBlock { nonce, .. } => *nonce,
                 },
+                version_bits: match &submission.share_type {
+                    crate::shares::ShareType::ComputationProof { version_bits, .. } => *version_bits,
+                    crate::shares::ShareType::ValidBlock { version_bits, .. } => *version_bits,
+                },
                 difficulty: validation.difficulty,
                 timestamp: Utc::now(),
                 is_valid: true,
@@ -173,17 +274,20 @@ impl PoolCoordinator {
         };
         
         let blocks_found_24h = shares.iter().filter(|s| s.is_block).count() as u64;
-        
-        // Estimate hashrate (simplified)
-        let total_hashrate = total_difficulty as f64 / (self.config.share_window_hours as f64 * 3600.0);
-        
+
+        // Rolled up from each worker's own live hashrate estimate rather than
+        // derived separately from the share-window sum.
+        let total_hashrate = self.stats.total_hashrate().await;
+
+        let total_paid_24h = redis.total_paid_since(now - Duration::hours(24)).await?;
+
         let stats = PoolStats {
             total_hashrate,
             active_miners,
             shares_per_second,
             average_share_difficulty,
             blocks_found_24h,
-            total_paid_24h: 0, // Would be calculated from payout records
+            total_paid_24h,
             pool_fee_percent: self.config.fee_percent,
         };
         
@@ -210,31 +314,136 @@ impl PoolCoordinator {
             "blocks_found": reputation.blocks_found,
             "reputation_score": reputation.reputation_score,
             "is_active": miner.is_active,
+            "current_difficulty": miner.current_difficulty,
+            "self_reported_hashrate": self.stats.self_reported_hashrate(address).await,
         }))
     }
-    
+
+    /// Records a miner's self-reported hashrate (e.g. from the
+    /// `eth_submitHashrate`-style JSON-RPC call) for display in
+    /// `get_miner_stats`. Unlike `StatsTracker::record_accepted`'s
+    /// share-derived estimate, this number isn't independently verified.
+    pub async fn submit_hashrate(&self, address: &str, hashrate: f64) {
+        self.stats.record_self_reported_hashrate(address, hashrate).await;
+    }
+
+    /// Persists the vardiff controller's latest difficulty for `address` (the
+    /// same key `register_miner` saved the `MinerRecord` under), so it
+    /// survives a reconnect and shows up in `get_miner_stats`. A miner record
+    /// that hasn't been registered yet is left alone; it'll start at the
+    /// default difficulty once it authorizes.
+    pub async fn update_miner_difficulty(&self, address: &str, difficulty: u64) -> Result<()> {
+        let mut redis = self.redis.write().await;
+        if let Some(mut miner) = redis.get_miner(address).await? {
+            miner.current_difficulty = difficulty;
+            redis.save_miner(&miner).await?;
+        }
+        Ok(())
+    }
+
     async fn trigger_block_payout(&self, block_share: &ShareRecord) -> Result<()> {
-        // Calculate rewards for all miners in the share window
-        let now = Utc::now();
-        let window_start = now - Duration::hours(self.config.share_window_hours as i64);
-        
-        info!("Calculating payouts for block found at {}", block_share.timestamp);
-        
-        // This would trigger the payout manager to calculate and queue payouts
-        // In production, would be more sophisticated
+        // Held across the whole calculate-then-apply sequence below so two
+        // blocks found close together can't interleave their reads/writes of
+        // the shared PPLNS scoring state.
+        let _payout_guard = self.payout_trigger_lock.lock().await;
+
+        info!(
+            "Calculating payouts for block found by {} at {}",
+            block_share.miner_address, block_share.timestamp
+        );
+
+        // The block's own difficulty stands in for the network difficulty at
+        // the moment it was found, sizing the PPLNS scoring window.
+        let window_start = block_share.timestamp - Duration::hours(self.config.share_window_hours as i64);
+        let (payouts, window) = self.payout_manager
+            .calculate_payouts(self.config.block_reward, window_start, block_share.timestamp, block_share.difficulty)
+            .await?;
+
+        let released = self.payout_manager
+            .apply_pending_balances(payouts, self.config.min_payout)
+            .await?;
+        let queued = self.payout_manager.queue_payouts(released).await?;
+
+        info!(
+            "Block payout: {} payout(s) queued, {} reward units scored over window {} .. {}",
+            queued, window.total_units, window.start, window.end
+        );
+
         Ok(())
     }
     
+    /// Pings Redis and, on failure, rebuilds the connection with exponential
+    /// backoff. Intended to be polled on its own interval (analogous to
+    /// `run_maintenance`) so outages are detected and repaired even when no
+    /// share traffic is currently exercising the connection.
+    pub async fn check_redis_health(&self) -> Result<()> {
+        let ping_result = self.redis.write().await.ping().await;
+
+        match ping_result {
+            Ok(()) => {
+                metrics::REDIS_CONNECTION_UP.set(1.0);
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Redis health check failed ({}), attempting to reconnect", e);
+                metrics::REDIS_CONNECTION_UP.set(0.0);
+                self.reconnect_redis_with_backoff().await
+            }
+        }
+    }
+
+    async fn reconnect_redis_with_backoff(&self) -> Result<()> {
+        const MAX_ATTEMPTS: u32 = 5;
+        const INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_millis(500);
+        const MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+
+        let mut backoff = INITIAL_BACKOFF;
+        for attempt in 1..=MAX_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+
+            let reconnected = {
+                let mut redis = self.redis.write().await;
+                redis.reconnect().await.is_ok() && redis.ping().await.is_ok()
+            };
+
+            if reconnected {
+                info!("Redis connection restored after {} reconnect attempt(s)", attempt);
+                metrics::REDIS_CONNECTION_UP.set(1.0);
+                return Ok(());
+            }
+
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            warn!("Redis reconnect attempt {}/{} failed, backing off {:?}", attempt, MAX_ATTEMPTS, backoff);
+        }
+
+        error!("Redis still unreachable after {} reconnect attempts", MAX_ATTEMPTS);
+        Err(PoolError::Other(format!("Redis unreachable after {} reconnect attempts", MAX_ATTEMPTS)))
+    }
+
     pub async fn run_maintenance(&self) -> Result<()> {
         // Clean up old shares
         let cutoff = Utc::now() - Duration::hours(48);
-        let mut redis = self.redis.write().await;
-        let removed = redis.cleanup_old_shares(cutoff).await?;
-        
-        if removed > 0 {
-            debug!("Cleaned up {} old shares", removed);
+        {
+            let mut redis = self.redis.write().await;
+            let removed = redis.cleanup_old_shares(cutoff).await?;
+
+            if removed > 0 {
+                debug!("Cleaned up {} old shares", removed);
+            }
         }
-        
+
+        // Drive the payout pipeline: submit anything above the minimum payout,
+        // then advance confirmations for whatever's already been submitted.
+        let submitted = self.payout_manager.process_payouts(self.config.min_payout).await?;
+        if submitted > 0 {
+            info!("Submitted {} payouts", submitted);
+        }
+
+        let confirmed = self.payout_manager.confirm_payouts(self.config.payout_confirmation_depth).await?;
+        if confirmed > 0 {
+            info!("Confirmed {} payouts", confirmed);
+        }
+
         Ok(())
     }
 }
\ No newline at end of file
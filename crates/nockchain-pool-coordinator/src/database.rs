@@ -0,0 +1,5 @@
+pub mod redis_store;
+pub mod schema;
+
+pub use redis_store::RedisStore;
+pub use schema::*;
@@ -16,10 +16,27 @@ impl RedisStore {
     pub async fn new(redis_url: &str) -> Result<Self> {
         let client = Arc::new(redis::Client::open(redis_url)?);
         let conn = ConnectionManager::new(client.clone()).await?;
-        
+
         Ok(Self { client, conn })
     }
-    
+
+    /// Checks that the connection is actually alive, as opposed to merely
+    /// constructed. `ConnectionManager` hides most transient errors behind
+    /// its own retry loop, so a stuck connection can otherwise go unnoticed
+    /// until a real command fails.
+    pub async fn ping(&mut self) -> Result<()> {
+        redis::cmd("PING").query_async(&mut self.conn).await?;
+        Ok(())
+    }
+
+    /// Rebuilds the underlying `ConnectionManager` from the original client.
+    /// Used by the health-check task and by callers retrying once after a
+    /// transient `PoolError::Database`.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        self.conn = ConnectionManager::new(self.client.clone()).await?;
+        Ok(())
+    }
+
     // Miner operations
     pub async fn get_miner(&mut self, address: &str) -> Result<Option<MinerRecord>> {
         let key = format!("miner:{}", address);
@@ -74,20 +91,85 @@ impl RedisStore {
         let share_ids: Vec<String> = self.conn
             .zrangebyscore("shares:window", start.timestamp(), end.timestamp())
             .await?;
-        
+
         let mut shares = Vec::new();
         for id in share_ids {
             let key = format!("share:{}", id);
             let data: Option<String> = self.conn.get(&key).await?;
-            
+
             if let Some(json) = data {
                 shares.push(serde_json::from_str(&json)?);
             }
         }
-        
+
+        Ok(shares)
+    }
+
+    // Fetch up to `limit` shares at or before `before`, newest first. Used by
+    // PPLNS to walk the share log backward from a found block without pulling
+    // the whole history into memory at once.
+    pub async fn get_shares_before(&mut self, before: DateTime<Utc>, limit: usize) -> Result<Vec<ShareRecord>> {
+        let share_ids: Vec<String> = self.conn
+            .zrevrangebyscore_limit("shares:window", before.timestamp(), i64::MIN, 0, limit as isize)
+            .await?;
+
+        let mut shares = Vec::new();
+        for id in share_ids {
+            let key = format!("share:{}", id);
+            let data: Option<String> = self.conn.get(&key).await?;
+
+            if let Some(json) = data {
+                shares.push(serde_json::from_str(&json)?);
+            }
+        }
+
         Ok(shares)
     }
     
+    /// Atomically records that `share_id` has been seen, for dedup. Returns
+    /// `true` the first time a given id is recorded (the share should be
+    /// processed), `false` if it was already present (a replay). The key's
+    /// TTL mirrors `save_job`'s 1-hour job TTL so the dedup set cleans
+    /// itself up once the job it was submitted against could no longer be
+    /// current anyway.
+    pub async fn mark_share_seen(&mut self, share_id: &str) -> Result<bool> {
+        let key = format!("share:seen:{}", share_id);
+        let set: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(3600)
+            .query_async(&mut self.conn)
+            .await?;
+
+        Ok(set.is_some())
+    }
+
+    /// Bulk-checks which of `share_ids` were already scored into an earlier
+    /// block's PPLNS window, so overlapping windows can't pay the same
+    /// share twice.
+    pub async fn shares_scored(&mut self, share_ids: &[String]) -> Result<Vec<bool>> {
+        if share_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let flags: Vec<bool> = redis::cmd("SMISMEMBER")
+            .arg("shares:scored")
+            .arg(share_ids)
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(flags)
+    }
+
+    /// Marks `share_ids` as scored, excluding them from future PPLNS windows.
+    pub async fn mark_shares_scored(&mut self, share_ids: &[String]) -> Result<()> {
+        if share_ids.is_empty() {
+            return Ok(());
+        }
+        self.conn.sadd("shares:scored", share_ids).await?;
+        Ok(())
+    }
+
     // Job operations
     pub async fn save_job(&mut self, job: &JobTemplate) -> Result<()> {
         let key = format!("job:{}", job.id);
@@ -161,8 +243,179 @@ impl RedisStore {
         let removed: u64 = self.conn
             .zremrangebyscore("shares:window", 0, before.timestamp())
             .await?;
-        
+
         debug!("Cleaned up {} old shares", removed);
         Ok(removed)
     }
+
+    // Payout pipeline operations. Payouts are persisted as `payout:{idempotency_key}`
+    // hashes, with `payouts:pending` (scored by amount, so ZRANGEBYSCORE can filter by
+    // min_payout directly) and `payouts:submitted` tracking which stage each is in.
+    pub async fn save_payout(&mut self, payout: &Payout) -> Result<()> {
+        let key = format!("payout:{}", payout.idempotency_key);
+        let json = serde_json::to_string(payout)?;
+        self.conn.set(&key, json).await?;
+        Ok(())
+    }
+
+    pub async fn get_payout(&mut self, idempotency_key: &str) -> Result<Option<Payout>> {
+        let key = format!("payout:{}", idempotency_key);
+        let data: Option<String> = self.conn.get(&key).await?;
+
+        match data {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Persists a freshly calculated payout and adds it to the pending set,
+    /// unless `payout.idempotency_key` is already queued. Returns `false` in
+    /// that case so callers can skip it as an idempotent no-op.
+    pub async fn enqueue_pending_payout(&mut self, payout: &Payout) -> Result<bool> {
+        let key = format!("payout:{}", payout.idempotency_key);
+        let exists: bool = self.conn.exists(&key).await?;
+        if exists {
+            return Ok(false);
+        }
+
+        self.save_payout(payout).await?;
+        self.conn.zadd("payouts:pending", &payout.idempotency_key, payout.amount as f64).await?;
+        Ok(true)
+    }
+
+    /// Atomically pops up to `limit` pending payouts with `amount >= min_payout`
+    /// off the pending set. Runs as a Lua script so that two concurrent
+    /// maintenance ticks can never both claim the same payout.
+    pub async fn claim_pending_payouts(&mut self, min_payout: u64, limit: usize) -> Result<Vec<Payout>> {
+        const CLAIM_SCRIPT: &str = r#"
+            local key = KEYS[1]
+            local min_payout = tonumber(ARGV[1])
+            local limit = tonumber(ARGV[2])
+            local claimed = {}
+            local members = redis.call('ZRANGEBYSCORE', key, min_payout, '+inf', 'LIMIT', 0, limit)
+            for _, id in ipairs(members) do
+                redis.call('ZREM', key, id)
+                table.insert(claimed, id)
+            end
+            return claimed
+        "#;
+
+        let ids: Vec<String> = redis::Script::new(CLAIM_SCRIPT)
+            .key("payouts:pending")
+            .arg(min_payout)
+            .arg(limit as isize)
+            .invoke_async(&mut self.conn)
+            .await?;
+
+        let mut payouts = Vec::new();
+        for id in ids {
+            if let Some(payout) = self.get_payout(&id).await? {
+                payouts.push(payout);
+            }
+        }
+        Ok(payouts)
+    }
+
+    pub async fn mark_payout_submitted(&mut self, idempotency_key: &str, txid: &str) -> Result<()> {
+        if let Some(mut payout) = self.get_payout(idempotency_key).await? {
+            payout.status = PayoutStatus::Submitted { txid: txid.to_string(), confirmations: 0 };
+            payout.attempts += 1;
+            self.save_payout(&payout).await?;
+            self.conn.sadd("payouts:submitted", idempotency_key).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns a failed submission to `Pending` so the next maintenance tick
+    /// retries it, rather than leaving it stuck in `Failed`.
+    pub async fn requeue_payout(&mut self, idempotency_key: &str, reason: &str) -> Result<()> {
+        if let Some(mut payout) = self.get_payout(idempotency_key).await? {
+            payout.status = PayoutStatus::Failed { reason: reason.to_string() };
+            payout.attempts += 1;
+            self.save_payout(&payout).await?;
+
+            payout.status = PayoutStatus::Pending;
+            self.save_payout(&payout).await?;
+            self.conn.zadd("payouts:pending", idempotency_key, payout.amount as f64).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_submitted_payouts(&mut self) -> Result<Vec<Payout>> {
+        let ids: Vec<String> = self.conn.smembers("payouts:submitted").await?;
+        let mut payouts = Vec::new();
+        for id in ids {
+            if let Some(payout) = self.get_payout(&id).await? {
+                payouts.push(payout);
+            }
+        }
+        Ok(payouts)
+    }
+
+    /// Records an updated confirmation count for a submitted payout, promoting
+    /// it to `Confirmed` and dropping it from `payouts:submitted` once
+    /// `confirmations` reaches `confirmation_depth`. Returns whether it was
+    /// just confirmed.
+    pub async fn update_payout_confirmations(
+        &mut self,
+        idempotency_key: &str,
+        txid: &str,
+        confirmations: u32,
+        confirmation_depth: u32,
+    ) -> Result<bool> {
+        let Some(mut payout) = self.get_payout(idempotency_key).await? else {
+            return Ok(false);
+        };
+
+        if confirmations >= confirmation_depth {
+            payout.status = PayoutStatus::Confirmed { txid: txid.to_string() };
+            self.save_payout(&payout).await?;
+            self.conn.srem("payouts:submitted", idempotency_key).await?;
+            self.conn.zadd("payouts:paid_log", idempotency_key, Utc::now().timestamp()).await?;
+            return Ok(true);
+        }
+
+        payout.status = PayoutStatus::Submitted { txid: txid.to_string(), confirmations };
+        self.save_payout(&payout).await?;
+        Ok(false)
+    }
+
+    /// Counts of payouts in each pipeline stage, for the metrics layer.
+    pub async fn payout_status_counts(&mut self) -> Result<(u64, u64)> {
+        let pending: u64 = self.conn.zcard("payouts:pending").await?;
+        let submitted: u64 = self.conn.scard("payouts:submitted").await?;
+        Ok((pending, submitted))
+    }
+
+    /// Sum of payouts confirmed since `since`, for `PoolStats::total_paid_24h`.
+    pub async fn total_paid_since(&mut self, since: DateTime<Utc>) -> Result<u64> {
+        let ids: Vec<String> = self.conn
+            .zrangebyscore("payouts:paid_log", since.timestamp(), Utc::now().timestamp())
+            .await?;
+
+        let mut total = 0u64;
+        for id in ids {
+            if let Some(payout) = self.get_payout(&id).await? {
+                total += payout.amount;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Adds `amount` to a miner's carried-forward PPLNS balance, returning
+    /// the new total. Used to accumulate rewards across blocks until they
+    /// cross `min_payout` (see `PayoutManager::apply_pending_balances`).
+    pub async fn accumulate_pending_balance(&mut self, miner_address: &str, amount: u64) -> Result<u64> {
+        let key = format!("miner:{}:pending_balance", miner_address);
+        let total: u64 = self.conn.incr(&key, amount).await?;
+        Ok(total)
+    }
+
+    /// Zeroes a miner's carried-forward balance once it's been released as
+    /// an actual payout.
+    pub async fn reset_pending_balance(&mut self, miner_address: &str) -> Result<()> {
+        let key = format!("miner:{}:pending_balance", miner_address);
+        self.conn.set(&key, 0u64).await?;
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -13,6 +13,15 @@ pub struct MinerRecord {
     pub total_difficulty: u128,
     pub registration_time: DateTime<Utc>,
     pub is_active: bool,
+    /// Difficulty the vardiff controller currently has assigned to this
+    /// miner's connection, persisted so it survives a reconnect and can be
+    /// reported via `get_miner_stats`.
+    #[serde(default = "default_difficulty")]
+    pub current_difficulty: u64,
+}
+
+fn default_difficulty() -> u64 {
+    1
 }
 
 impl MinerRecord {
@@ -27,6 +36,7 @@ impl MinerRecord {
             total_difficulty: 0,
             registration_time: now,
             is_active: true,
+            current_difficulty: default_difficulty(),
         }
     }
 }
@@ -37,6 +47,10 @@ pub struct ShareRecord {
     pub miner_address: String,
     pub job_id: String,
     pub nonce: u64,
+    /// Version-rolling bits the miner searched alongside `nonce`, within
+    /// the mask it was granted via `mining.configure`.
+    #[serde(default)]
+    pub version_bits: u32,
     pub difficulty: u64,
     pub timestamp: DateTime<Utc>,
     pub is_valid: bool,
@@ -74,18 +88,68 @@ impl JobTemplate {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PayoutQueue {
-    pub pending_payouts: Vec<PendingPayout>,
-    pub last_payout_time: DateTime<Utc>,
-    pub total_paid: u64,
+pub struct PendingPayout {
+    pub miner_address: String,
+    pub amount: u64,
+    pub shares_window: (DateTime<Utc>, DateTime<Utc>),
+    pub share_count: u64,
+}
+
+/// Where a queued payout sits in the on-chain submission pipeline.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PayoutStatus {
+    /// Queued, not yet claimed by a maintenance tick.
+    Pending,
+    /// A transaction was broadcast; waiting for `confirmations` to reach the
+    /// configured confirmation depth.
+    Submitted { txid: String, confirmations: u32 },
+    /// Reached the required confirmation depth.
+    Confirmed { txid: String },
+    /// Submission failed; `reason` is kept for diagnostics, but the payout
+    /// itself is returned to `Pending` for retry rather than left here.
+    Failed { reason: String },
 }
 
+/// A durable, idempotent unit of the payout pipeline. Unlike [`PendingPayout`]
+/// (an ephemeral calculation result), a `Payout` is persisted in Redis keyed
+/// by `idempotency_key` so a crash or a double-run of maintenance can never
+/// queue or pay the same miner+window twice.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PendingPayout {
+pub struct Payout {
+    pub idempotency_key: String,
     pub miner_address: String,
     pub amount: u64,
     pub shares_window: (DateTime<Utc>, DateTime<Utc>),
     pub share_count: u64,
+    pub status: PayoutStatus,
+    pub created_at: DateTime<Utc>,
+    pub attempts: u32,
+}
+
+impl Payout {
+    /// Deterministic key for a miner+window pair, so re-queuing the same
+    /// calculation (e.g. after a restart) is a no-op rather than a double-pay.
+    pub fn idempotency_key_for(miner_address: &str, shares_window: (DateTime<Utc>, DateTime<Utc>)) -> String {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(miner_address.as_bytes());
+        buf.extend_from_slice(&shares_window.0.timestamp().to_le_bytes());
+        buf.extend_from_slice(&shares_window.1.timestamp().to_le_bytes());
+        hex::encode(sha2::Sha256::digest(&buf))
+    }
+
+    pub fn from_pending(pending: PendingPayout) -> Self {
+        let idempotency_key = Self::idempotency_key_for(&pending.miner_address, pending.shares_window);
+        Self {
+            idempotency_key,
+            miner_address: pending.miner_address,
+            amount: pending.amount,
+            shares_window: pending.shares_window,
+            share_count: pending.share_count,
+            status: PayoutStatus::Pending,
+            created_at: Utc::now(),
+            attempts: 0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,9 +28,15 @@ pub enum PoolError {
     
     #[error("Insufficient difficulty")]
     InsufficientDifficulty,
+
+    #[error("Submitted version bits are outside the granted version-rolling mask")]
+    VersionRollingViolation,
     
     #[error("Job not found: {0}")]
     JobNotFound(String),
+
+    #[error("Share submitted against stale job: {0}")]
+    StaleShare(String),
     
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
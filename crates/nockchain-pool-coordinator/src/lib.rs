@@ -5,6 +5,8 @@ pub mod stratum;
 pub mod coordinator;
 pub mod payout;
 pub mod metrics;
+pub mod stats;
+pub mod rpc;
 
 pub use coordinator::PoolCoordinator;
 pub use error::{PoolError, Result};
\ No newline at end of file
@@ -1,4 +1,4 @@
-use prometheus::{Encoder, TextEncoder, Counter, Gauge, Histogram, HistogramOpts};
+use prometheus::{Encoder, TextEncoder, Counter, Gauge, GaugeVec, Histogram, HistogramOpts, IntCounterVec, Opts};
 use lazy_static::lazy_static;
 
 lazy_static! {
@@ -35,6 +35,51 @@ lazy_static! {
     pub static ref SHARE_VALIDATION_TIME: Histogram = Histogram::with_opts(
         HistogramOpts::new("pool_share_validation_seconds", "Time to validate shares")
     ).unwrap();
+
+    // Per-worker labeled metrics backing the `stats` module.
+    pub static ref WORKER_SHARES_ACCEPTED: IntCounterVec = IntCounterVec::new(
+        Opts::new("pool_worker_shares_accepted_total", "Accepted shares per worker"),
+        &["miner"]
+    ).unwrap();
+
+    pub static ref WORKER_SHARES_REJECTED: IntCounterVec = IntCounterVec::new(
+        Opts::new("pool_worker_shares_rejected_total", "Rejected shares per worker, broken out by reason"),
+        &["miner", "reason"]
+    ).unwrap();
+
+    pub static ref WORKER_HASHRATE: GaugeVec = GaugeVec::new(
+        Opts::new("pool_worker_hashrate_hps", "Estimated per-worker hashrate in hashes per second"),
+        &["miner"]
+    ).unwrap();
+
+    // Payout pipeline state, refreshed by the payout module after each
+    // queue/process/confirm pass.
+    pub static ref PAYOUTS_PENDING: Gauge = Gauge::new(
+        "pool_payouts_pending",
+        "Payouts queued but not yet submitted on-chain"
+    ).unwrap();
+
+    pub static ref PAYOUTS_SUBMITTED: Gauge = Gauge::new(
+        "pool_payouts_submitted",
+        "Payouts submitted on-chain, awaiting confirmation depth"
+    ).unwrap();
+
+    pub static ref PAYOUTS_CONFIRMED_TOTAL: Counter = Counter::new(
+        "pool_payouts_confirmed_total",
+        "Total payouts that reached the confirmation depth"
+    ).unwrap();
+
+    pub static ref PAYOUTS_FAILED_TOTAL: Counter = Counter::new(
+        "pool_payouts_failed_total",
+        "Total payout submission attempts that failed and were retried"
+    ).unwrap();
+
+    /// 1 if the last Redis health check succeeded, 0 if the connection is
+    /// currently considered down.
+    pub static ref REDIS_CONNECTION_UP: Gauge = Gauge::new(
+        "pool_redis_connection_up",
+        "Whether the pool coordinator's Redis connection is healthy"
+    ).unwrap();
 }
 
 pub fn register_metrics() {
@@ -45,6 +90,14 @@ pub fn register_metrics() {
     prometheus::register(Box::new(ACTIVE_MINERS.clone())).unwrap();
     prometheus::register(Box::new(POOL_HASHRATE.clone())).unwrap();
     prometheus::register(Box::new(SHARE_VALIDATION_TIME.clone())).unwrap();
+    prometheus::register(Box::new(WORKER_SHARES_ACCEPTED.clone())).unwrap();
+    prometheus::register(Box::new(WORKER_SHARES_REJECTED.clone())).unwrap();
+    prometheus::register(Box::new(WORKER_HASHRATE.clone())).unwrap();
+    prometheus::register(Box::new(PAYOUTS_PENDING.clone())).unwrap();
+    prometheus::register(Box::new(PAYOUTS_SUBMITTED.clone())).unwrap();
+    prometheus::register(Box::new(PAYOUTS_CONFIRMED_TOTAL.clone())).unwrap();
+    prometheus::register(Box::new(PAYOUTS_FAILED_TOTAL.clone())).unwrap();
+    prometheus::register(Box::new(REDIS_CONNECTION_UP.clone())).unwrap();
 }
 
 pub fn metrics_handler() -> String {
@@ -4,99 +4,471 @@ use tokio::sync::RwLock;
 use chrono::{DateTime, Utc, Duration};
 use tracing::{info, warn, debug};
 
-use crate::database::{RedisStore, ShareRecord, PendingPayout, PayoutQueue};
-use crate::error::Result;
+use crate::database::{RedisStore, Payout, PayoutStatus, PendingPayout};
+use crate::error::{PoolError, Result};
+use crate::metrics;
+
+/// A non-miner address entitled to a cut of the pool fee (operator, dev fund,
+/// infrastructure, ...). The sum of `percent` across all recipients must equal
+/// the pool's `fee_percent`.
+#[derive(Debug, Clone)]
+pub struct RewardRecipient {
+    pub address: String,
+    pub percent: f64,
+}
+
+/// How rewards are split among the miners who contributed shares to a block.
+#[derive(Debug, Clone)]
+pub enum PayoutScheme {
+    /// Split proportionally over all shares submitted in a fixed time window.
+    Proportional,
+    /// Pay-Per-Last-N-Shares: split over the last `n_factor * network_difficulty`
+    /// reward units of share log, regardless of how long that window spans.
+    /// Resistant to pool-hopping since a miner's payout share only depends on
+    /// the shares they actually contributed within the scoring window,
+    /// weighted by their reputation score.
+    Pplns { n_factor: f64 },
+}
+
+impl Default for PayoutScheme {
+    fn default() -> Self {
+        PayoutScheme::Proportional
+    }
+}
+
+/// The share-log window a payout calculation actually drew from, so callers
+/// (metrics, logging) can report what was paid out over.
+#[derive(Debug, Clone)]
+pub struct PayoutWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub total_units: u64,
+}
 
 pub struct PayoutManager {
     redis: Arc<RwLock<RedisStore>>,
     pool_fee_percent: f64,
+    payout_scheme: PayoutScheme,
+    reward_recipients: Vec<RewardRecipient>,
 }
 
 impl PayoutManager {
     pub fn new(redis: Arc<RwLock<RedisStore>>, pool_fee_percent: f64) -> Self {
+        Self::with_scheme(redis, pool_fee_percent, PayoutScheme::default())
+    }
+
+    pub fn with_scheme(
+        redis: Arc<RwLock<RedisStore>>,
+        pool_fee_percent: f64,
+        payout_scheme: PayoutScheme,
+    ) -> Self {
         Self {
             redis,
             pool_fee_percent,
+            payout_scheme,
+            reward_recipients: Vec::new(),
         }
     }
-    
+
+    /// Like [`Self::with_scheme`] but also splits the pool fee among `reward_recipients`.
+    /// Fails if the recipients' percentages don't sum to `pool_fee_percent`.
+    pub fn with_recipients(
+        redis: Arc<RwLock<RedisStore>>,
+        pool_fee_percent: f64,
+        payout_scheme: PayoutScheme,
+        reward_recipients: Vec<RewardRecipient>,
+    ) -> Result<Self> {
+        const EPSILON: f64 = 1e-6;
+
+        if !reward_recipients.is_empty() {
+            let total_percent: f64 = reward_recipients.iter().map(|r| r.percent).sum();
+            if (total_percent - pool_fee_percent).abs() > EPSILON {
+                return Err(PoolError::Configuration(format!(
+                    "reward_recipients percentages sum to {}, expected fee_percent {}",
+                    total_percent, pool_fee_percent
+                )));
+            }
+        }
+
+        Ok(Self {
+            redis,
+            pool_fee_percent,
+            payout_scheme,
+            reward_recipients,
+        })
+    }
+
+    /// Pending payouts for each reward recipient, drawn from `pool_fee`
+    /// proportionally to their configured share of the total fee.
+    fn recipient_payouts(&self, pool_fee: u64, shares_window: (DateTime<Utc>, DateTime<Utc>)) -> Vec<PendingPayout> {
+        if self.pool_fee_percent <= 0.0 {
+            return Vec::new();
+        }
+
+        self.reward_recipients
+            .iter()
+            .filter_map(|recipient| {
+                let amount = (pool_fee as f64 * recipient.percent / self.pool_fee_percent) as u64;
+                if amount == 0 {
+                    return None;
+                }
+                Some(PendingPayout {
+                    miner_address: recipient.address.clone(),
+                    amount,
+                    shares_window,
+                    share_count: 0,
+                })
+            })
+            .collect()
+    }
+
     pub async fn calculate_payouts(
         &self,
         block_reward: u64,
         window_start: DateTime<Utc>,
         window_end: DateTime<Utc>,
-    ) -> Result<Vec<PendingPayout>> {
+        network_difficulty: u64,
+    ) -> Result<(Vec<PendingPayout>, PayoutWindow)> {
+        match &self.payout_scheme {
+            PayoutScheme::Proportional => {
+                self.calculate_proportional_payouts(block_reward, window_start, window_end).await
+            }
+            PayoutScheme::Pplns { n_factor } => {
+                self.calculate_pplns_payouts(block_reward, window_end, *n_factor, network_difficulty).await
+            }
+        }
+    }
+
+    async fn calculate_proportional_payouts(
+        &self,
+        block_reward: u64,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+    ) -> Result<(Vec<PendingPayout>, PayoutWindow)> {
         let mut redis = self.redis.write().await;
         let shares = redis.get_shares_in_window(window_start, window_end).await?;
-        
+
         // Calculate total reward units
         let total_units: u64 = shares.iter().map(|s| s.reward_units).sum();
         if total_units == 0 {
-            return Ok(Vec::new());
+            return Ok((Vec::new(), PayoutWindow { start: window_start, end: window_end, total_units: 0 }));
         }
-        
+
         // Calculate pool fee
         let pool_fee = (block_reward as f64 * self.pool_fee_percent / 100.0) as u64;
         let distributable_reward = block_reward - pool_fee;
-        
+
         // Group shares by miner
-        let mut miner_units: HashMap<String, u64> = HashMap::new();
+        let mut miner_units: HashMap<String, f64> = HashMap::new();
         let mut miner_shares: HashMap<String, u64> = HashMap::new();
-        
+
         for share in shares {
-            *miner_units.entry(share.miner_address.clone()).or_insert(0) += share.reward_units;
+            *miner_units.entry(share.miner_address.clone()).or_insert(0.0) += share.reward_units as f64;
             *miner_shares.entry(share.miner_address.clone()).or_insert(0) += 1;
         }
-        
-        // Calculate payouts
+
+        let mut payouts = self.recipient_payouts(pool_fee, (window_start, window_end));
+        payouts.extend(self.build_payouts(
+            distributable_reward,
+            total_units as f64,
+            &miner_units,
+            &miner_shares,
+            (window_start, window_end),
+        ));
+
+        info!(
+            "Calculated proportional payouts for {} miners, total: {}, pool fee: {}",
+            payouts.len(),
+            distributable_reward,
+            pool_fee
+        );
+
+        Ok((payouts, PayoutWindow { start: window_start, end: window_end, total_units }))
+    }
+
+    // Walks backward from the block's share, accumulating reward units until
+    // the window reaches `n_factor * network_difficulty`. Shares older than
+    // that window contribute nothing; a share submitted before the previous
+    // block but still inside this window counts normally.
+    async fn calculate_pplns_payouts(
+        &self,
+        block_reward: u64,
+        block_time: DateTime<Utc>,
+        n_factor: f64,
+        network_difficulty: u64,
+    ) -> Result<(Vec<PendingPayout>, PayoutWindow)> {
+        const BATCH_SIZE: usize = 500;
+
+        let target_units = (n_factor * network_difficulty as f64) as u64;
+        let mut redis = self.redis.write().await;
+
+        let mut accumulated: u64 = 0;
+        // Reputation-weighted units, used for the actual payout split — a
+        // share still costs its full `reward_units` against the N-window
+        // (so the window always represents `n_factor * network_difficulty`
+        // of real work), but a miner with a poor reputation score is paid
+        // less per unit than one with a strong one.
+        let mut miner_units: HashMap<String, f64> = HashMap::new();
+        let mut miner_shares: HashMap<String, u64> = HashMap::new();
+        let mut reputation_cache: HashMap<String, f64> = HashMap::new();
+        let mut weighted_total: f64 = 0.0;
+        let mut window_start = block_time;
+        let mut cursor = block_time;
+
+        while accumulated < target_units {
+            let batch = redis.get_shares_before(cursor, BATCH_SIZE).await?;
+            if batch.is_empty() {
+                break;
+            }
+
+            let batch_ids: Vec<String> = batch.iter().map(|s| s.id.clone()).collect();
+            let already_scored = redis.shares_scored(&batch_ids).await?;
+
+            // Marked as scored below, per batch rather than once after the
+            // whole loop -- `cursor` advances to this batch's own oldest
+            // share so the next page's inclusive-max query re-fetches it,
+            // and only an immediate mark makes `already_scored` catch that
+            // re-fetch and skip it instead of double-counting it.
+            let mut batch_scored_ids: Vec<String> = Vec::new();
+            for (share, scored) in batch.iter().zip(already_scored.iter()) {
+                if accumulated >= target_units {
+                    break;
+                }
+                if *scored {
+                    continue;
+                }
+
+                let remaining = target_units - accumulated;
+                let units = share.reward_units.min(remaining);
+
+                let reputation = match reputation_cache.get(&share.miner_address) {
+                    Some(score) => *score,
+                    None => {
+                        let score = redis
+                            .get_reputation(&share.miner_address)
+                            .await?
+                            .map(|r| r.reputation_score)
+                            .unwrap_or(1.0);
+                        reputation_cache.insert(share.miner_address.clone(), score);
+                        score
+                    }
+                };
+                let weighted_units = units as f64 * reputation;
+
+                *miner_units.entry(share.miner_address.clone()).or_insert(0.0) += weighted_units;
+                *miner_shares.entry(share.miner_address.clone()).or_insert(0) += 1;
+                weighted_total += weighted_units;
+                accumulated += units;
+                window_start = share.timestamp;
+                batch_scored_ids.push(share.id.clone());
+            }
+
+            if !batch_scored_ids.is_empty() {
+                // Marked before the next page is fetched, so that page's
+                // `already_scored` check catches this batch's boundary share
+                // if the inclusive-max re-query returns it again.
+                redis.mark_shares_scored(&batch_scored_ids).await?;
+            }
+
+            if batch.len() < BATCH_SIZE {
+                break;
+            }
+            cursor = batch.last().expect("non-empty batch").timestamp;
+        }
+
+        if accumulated == 0 {
+            return Ok((Vec::new(), PayoutWindow { start: window_start, end: block_time, total_units: 0 }));
+        }
+
+        let pool_fee = (block_reward as f64 * self.pool_fee_percent / 100.0) as u64;
+        let distributable_reward = block_reward - pool_fee;
+
+        let mut payouts = self.recipient_payouts(pool_fee, (window_start, block_time));
+        payouts.extend(self.build_payouts(
+            distributable_reward,
+            weighted_total,
+            &miner_units,
+            &miner_shares,
+            (window_start, block_time),
+        ));
+
+        info!(
+            "Calculated PPLNS payouts for {} miners over {} reward units (N = {}), pool fee: {}",
+            payouts.len(),
+            accumulated,
+            target_units,
+            pool_fee
+        );
+
+        Ok((payouts, PayoutWindow { start: window_start, end: block_time, total_units: accumulated }))
+    }
+
+    fn build_payouts(
+        &self,
+        distributable_reward: u64,
+        total_units: f64,
+        miner_units: &HashMap<String, f64>,
+        miner_shares: &HashMap<String, u64>,
+        shares_window: (DateTime<Utc>, DateTime<Utc>),
+    ) -> Vec<PendingPayout> {
         let mut payouts = Vec::new();
         for (miner_address, units) in miner_units {
-            let miner_reward = (distributable_reward as f64 * units as f64 / total_units as f64) as u64;
-            
+            let miner_reward = (distributable_reward as f64 * *units / total_units) as u64;
+
             if miner_reward > 0 {
                 payouts.push(PendingPayout {
-                    miner_address,
+                    miner_address: miner_address.clone(),
                     amount: miner_reward,
-                    shares_window: (window_start, window_end),
-                    share_count: miner_shares.get(&miner_address).copied().unwrap_or(0),
+                    shares_window,
+                    share_count: miner_shares.get(miner_address).copied().unwrap_or(0),
                 });
             }
         }
-        
-        info!(
-            "Calculated payouts for {} miners, total: {}, pool fee: {}",
-            payouts.len(),
-            distributable_reward,
-            pool_fee
-        );
-        
-        Ok(payouts)
+        payouts
     }
-    
-    pub async fn queue_payouts(&self, payouts: Vec<PendingPayout>) -> Result<()> {
+
+    /// Folds each computed payout's amount into that miner's carried-forward
+    /// balance, releasing a payout for a miner only once their balance
+    /// reaches `min_payout`. Any remainder stays banked for the next block's
+    /// calculation, so a string of small PPLNS payouts eventually clears
+    /// instead of getting stuck forever below the claim threshold.
+    pub async fn apply_pending_balances(
+        &self,
+        payouts: Vec<PendingPayout>,
+        min_payout: u64,
+    ) -> Result<Vec<PendingPayout>> {
         let mut redis = self.redis.write().await;
-        
-        // Get or create payout queue
-        let mut queue = PayoutQueue {
-            pending_payouts: payouts,
-            last_payout_time: Utc::now(),
-            total_paid: 0,
-        };
-        
-        // In production, would save to Redis
-        debug!("Queued {} payouts", queue.pending_payouts.len());
-        
-        Ok(())
+
+        let mut released = Vec::with_capacity(payouts.len());
+        for mut payout in payouts {
+            let balance = redis
+                .accumulate_pending_balance(&payout.miner_address, payout.amount)
+                .await?;
+
+            if balance >= min_payout {
+                redis.reset_pending_balance(&payout.miner_address).await?;
+                payout.amount = balance;
+                released.push(payout);
+            } else {
+                debug!(
+                    "Miner {} balance {} below min payout {}, carrying forward",
+                    payout.miner_address, balance, min_payout
+                );
+            }
+        }
+
+        Ok(released)
+    }
+
+    /// Persists each calculated payout under its deterministic idempotency key.
+    /// Re-queuing a payout already seen for the same miner+window is a no-op,
+    /// so a restart replaying `trigger_block_payout` can never double-pay.
+    pub async fn queue_payouts(&self, payouts: Vec<PendingPayout>) -> Result<u64> {
+        let mut redis = self.redis.write().await;
+
+        let mut queued = 0;
+        for pending in payouts {
+            let payout = Payout::from_pending(pending);
+            if redis.enqueue_pending_payout(&payout).await? {
+                queued += 1;
+            } else {
+                debug!("Payout {} already queued, skipping", payout.idempotency_key);
+            }
+        }
+
+        self.refresh_payout_metrics(&mut redis).await?;
+        debug!("Queued {} new payouts", queued);
+        Ok(queued)
     }
-    
+
+    /// Atomically claims pending payouts with `amount >= min_payout` and
+    /// submits each one. Claiming happens via a Redis Lua script so two
+    /// concurrent maintenance ticks never pick up the same payout, and a
+    /// failed submission is returned to `Pending` for retry on the next tick
+    /// rather than lost.
     pub async fn process_payouts(&self, min_payout: u64) -> Result<u64> {
-        // In production, this would:
-        // 1. Get pending payouts from queue
-        // 2. Filter by minimum payout amount
-        // 3. Create blockchain transactions
-        // 4. Track payment status
-        
-        warn!("Payout processing not implemented in this MVP");
-        Ok(0)
+        const CLAIM_BATCH: usize = 100;
+
+        let claimed = {
+            let mut redis = self.redis.write().await;
+            redis.claim_pending_payouts(min_payout, CLAIM_BATCH).await?
+        };
+
+        let mut submitted = 0;
+        for payout in claimed {
+            match self.submit_payout_transaction(&payout).await {
+                Ok(txid) => {
+                    let mut redis = self.redis.write().await;
+                    redis.mark_payout_submitted(&payout.idempotency_key, &txid).await?;
+                    info!(
+                        "Submitted payout {} ({} to {}), txid {}",
+                        payout.idempotency_key, payout.amount, payout.miner_address, txid
+                    );
+                    submitted += 1;
+                }
+                Err(reason) => {
+                    warn!(
+                        "Payout {} submission failed ({}), returning to pending for retry",
+                        payout.idempotency_key, reason
+                    );
+                    metrics::PAYOUTS_FAILED_TOTAL.inc();
+                    let mut redis = self.redis.write().await;
+                    redis.requeue_payout(&payout.idempotency_key, &reason).await?;
+                }
+            }
+        }
+
+        let mut redis = self.redis.write().await;
+        self.refresh_payout_metrics(&mut redis).await?;
+        Ok(submitted)
+    }
+
+    /// Advances submitted payouts' confirmation counts, promoting each to
+    /// `Confirmed` once it reaches `confirmation_depth`. Returns the number
+    /// confirmed in this pass.
+    pub async fn confirm_payouts(&self, confirmation_depth: u32) -> Result<u64> {
+        let mut redis = self.redis.write().await;
+        let submitted = redis.get_submitted_payouts().await?;
+
+        let mut confirmed = 0;
+        for payout in submitted {
+            let PayoutStatus::Submitted { txid, confirmations } = &payout.status else {
+                continue;
+            };
+
+            // In production this would query the chain for the transaction's
+            // actual confirmation count; here we simulate one confirmation
+            // landing per maintenance tick.
+            let confirmations = confirmations + 1;
+            if redis
+                .update_payout_confirmations(&payout.idempotency_key, txid, confirmations, confirmation_depth)
+                .await?
+            {
+                info!("Payout {} confirmed (txid {})", payout.idempotency_key, txid);
+                metrics::PAYOUTS_CONFIRMED_TOTAL.inc();
+                confirmed += 1;
+            }
+        }
+
+        self.refresh_payout_metrics(&mut redis).await?;
+        Ok(confirmed)
+    }
+
+    /// Builds and broadcasts the on-chain transaction for a claimed payout,
+    /// returning its txid. In production this would construct a real
+    /// transaction against the miner's address; this MVP deterministically
+    /// derives a stand-in txid instead.
+    async fn submit_payout_transaction(&self, payout: &Payout) -> std::result::Result<String, String> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(payout.idempotency_key.as_bytes());
+        buf.extend_from_slice(&payout.attempts.to_le_bytes());
+        Ok(format!("0x{}", hex::encode(sha2::Sha256::digest(&buf))))
     }
-}
\ No newline at end of file
+
+    async fn refresh_payout_metrics(&self, redis: &mut RedisStore) -> Result<()> {
+        let (pending, submitted) = redis.payout_status_counts().await?;
+        metrics::PAYOUTS_PENDING.set(pending as f64);
+        metrics::PAYOUTS_SUBMITTED.set(submitted as f64);
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::coordinator::PoolCoordinator;
+use crate::shares::{ShareSubmission, ShareType};
+
+/// Lightweight JSON-RPC 2.0 interface for miners that don't speak the
+/// Stratum protocol -- the classic `getWork`/`submitWork` pair, plus an
+/// `eth_submitHashrate`-style call for self-reported hashrate display.
+/// Submissions flow through the same `PoolCoordinator::submit_share` choke
+/// point the Stratum path uses, so accounting and metrics stay consistent
+/// between the two.
+pub fn router(coordinator: Arc<PoolCoordinator>) -> Router {
+    Router::new()
+        .route("/rpc", post(rpc_handler))
+        .with_state(coordinator)
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i32,
+    message: String,
+}
+
+/// JSON-RPC error codes: the standard reserved codes, plus -32000 (from the
+/// implementation-defined server-error range) for internal failures. A
+/// rejected share is not an error; `submitWork` reports it in its result.
+mod error_code {
+    pub const METHOD_NOT_FOUND: i32 = -32601;
+    pub const INVALID_PARAMS: i32 = -32602;
+    pub const INTERNAL: i32 = -32000;
+}
+
+async fn rpc_handler(
+    State(coordinator): State<Arc<PoolCoordinator>>,
+    Json(req): Json<RpcRequest>,
+) -> Json<RpcResponse> {
+    let id = req.id.clone();
+    let result = match req.method.as_str() {
+        "getWork" => get_work(&coordinator).await,
+        "submitWork" => submit_work(&coordinator, req.params).await,
+        "eth_submitHashrate" => submit_hashrate(&coordinator, req.params).await,
+        other => Err((error_code::METHOD_NOT_FOUND, format!("unknown method: {}", other))),
+    };
+
+    Json(match result {
+        Ok(result) => RpcResponse {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        },
+        Err((code, message)) => RpcResponse {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(RpcError { code, message }),
+        },
+    })
+}
+
+/// Reshapes the current `JobTemplate` into a classic getWork package: a job
+/// id to echo back in `submitWork`, the block commitment to search against,
+/// and the target difficulty a valid nonce must clear.
+async fn get_work(coordinator: &PoolCoordinator) -> Result<Value, (i32, String)> {
+    let job = coordinator
+        .get_current_job()
+        .await
+        .map_err(|e| (error_code::INTERNAL, e.to_string()))?
+        .ok_or_else(|| (error_code::INTERNAL, "no current job available".to_string()))?;
+
+    Ok(serde_json::json!({
+        "job_id": job.id,
+        "block_commitment": hex::encode(&job.block_commitment),
+        "target": hex::encode(&job.target),
+        "height": job.height,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct SubmitWorkParams {
+    job_id: String,
+    miner_id: String,
+    nonce: u64,
+    witness_commitment: String,
+    computation_steps: u64,
+    #[serde(default = "default_difficulty")]
+    difficulty: u64,
+}
+
+fn default_difficulty() -> u64 {
+    1
+}
+
+/// Reconstructs a `ShareSubmission` from the getWork-style params and feeds
+/// it through `submit_share`, the same entry point the Stratum path uses --
+/// so this is scored, deduplicated, and counted toward payouts identically.
+async fn submit_work(coordinator: &PoolCoordinator, params: Value) -> Result<Value, (i32, String)> {
+    let params: SubmitWorkParams = serde_json::from_value(params)
+        .map_err(|e| (error_code::INVALID_PARAMS, format!("invalid params: {}", e)))?;
+
+    let witness_commitment = decode_commitment(&params.witness_commitment)
+        .map_err(|e| (error_code::INVALID_PARAMS, e))?;
+
+    // There's no separate Stratum-style `Authorize` step on this path, so
+    // register the miner (idempotent upsert, mirroring the Stratum
+    // `Authorize` handler) on every submission -- otherwise a miner that
+    // only ever speaks JSON-RPC never gets a `MinerRecord` and `get_miner_stats`
+    // 404s for it forever, even after it's earned payouts.
+    coordinator
+        .register_miner(&params.miner_id, &params.miner_id)
+        .await
+        .map_err(|e| (error_code::INTERNAL, e.to_string()))?;
+
+    let submission = ShareSubmission {
+        job_id: params.job_id,
+        miner_id: params.miner_id.clone(),
+        share_type: ShareType::ComputationProof {
+            nonce: params.nonce,
+            witness_commitment,
+            computation_steps: params.computation_steps,
+            version_bits: 0,
+            extranonce2: String::new(),
+        },
+    };
+
+    match coordinator.submit_share(submission, params.difficulty).await {
+        Ok(validation) => Ok(serde_json::json!({
+            "accepted": true,
+            "is_block": validation.is_block,
+        })),
+        Err(e) => {
+            debug!("getWork share from {} rejected: {}", params.miner_id, e);
+            Ok(serde_json::json!({
+                "accepted": false,
+                "reason": e.to_string(),
+            }))
+        }
+    }
+}
+
+fn decode_commitment(s: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(s).map_err(|e| format!("invalid witness_commitment hex: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|_| "witness_commitment must be 32 bytes".to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct SubmitHashrateParams {
+    miner_id: String,
+    hashrate: f64,
+}
+
+async fn submit_hashrate(coordinator: &PoolCoordinator, params: Value) -> Result<Value, (i32, String)> {
+    let params: SubmitHashrateParams = serde_json::from_value(params)
+        .map_err(|e| (error_code::INVALID_PARAMS, format!("invalid params: {}", e)))?;
+
+    coordinator.submit_hashrate(&params.miner_id, params.hashrate).await;
+
+    Ok(Value::Bool(true))
+}
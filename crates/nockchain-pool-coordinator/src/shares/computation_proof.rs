@@ -4,81 +4,182 @@ use nockvm::noun::{Atom, Noun, D, T};
 use nockapp::noun::slab::NounSlab;
 use tracing::debug;
 
+/// A batch proof of work over a nonce range, position-bound via a Merkle
+/// commitment: `witness_commitment` is the root over `leaves` (one per
+/// sampled nonce, in nonce order), and `verify` checks a deterministically
+/// chosen subset of them against the root using `auth_paths` rather than
+/// trusting any single hash in isolation.
 #[derive(Debug, Clone)]
 pub struct ComputationProof {
     pub witness_commitment: [u8; 32],
     pub nonce_range: Range<u64>,
     pub computation_steps: u64,
-    pub intermediate_hashes: Vec<[u8; 32]>,
+    /// Per-sampled-nonce witness hash, in nonce order -- the Merkle tree's leaves.
+    pub leaves: Vec<[u8; 32]>,
+    /// Sibling path from each leaf up to `witness_commitment`, indexed the
+    /// same as `leaves`.
+    pub auth_paths: Vec<Vec<[u8; 32]>>,
 }
 
-impl ComputationProof {
-    pub fn generate_for_range(
-        block_commitment: &[u8],
-        nonce_range: Range<u64>,
-        sample_rate: usize,
-    ) -> Self {
-        let mut hasher = Sha256::new();
-        let mut intermediate_hashes = Vec::new();
-        let mut computation_steps = 0;
-        
-        // Sample nonces from range
-        let step = ((nonce_range.end - nonce_range.start) / sample_rate as u64).max(1);
-        
-        for i in 0..sample_rate {
-            let nonce = nonce_range.start + (i as u64 * step);
-            if nonce >= nonce_range.end {
-                break;
-            }
-            
-            // Simulate STARK witness computation
-            let witness = compute_partial_witness(block_commitment, nonce);
-            let witness_bytes = witness_to_bytes(&witness);
-            hasher.update(&witness_bytes);
-            
-            // Store intermediate hash
-            let intermediate = hasher.clone().finalize();
-            intermediate_hashes.push(intermediate.into());
-            
-            computation_steps += estimate_computation_steps(&witness_bytes);
-        }
-        
-        ComputationProof {
-            witness_commitment: hasher.finalize().into(),
-            nonce_range,
-            computation_steps,
-            intermediate_hashes,
+/// Tuning knobs for spreading per-nonce witness computation across worker
+/// threads. Mirrors how solo mining sizes its own worker pool (see
+/// `nockchain::mining::resolve_mining_threads`): plain `std::thread`s, one
+/// per core, with affinity pinning available as an opt-in refinement.
+#[derive(Debug, Clone, Copy)]
+pub struct ComputationProofConfig {
+    pub threads: usize,
+    /// Pin each worker thread to a distinct physical core via `core_affinity`.
+    /// No-ops on platforms where core information isn't available.
+    pub pin_cores: bool,
+}
+
+impl Default for ComputationProofConfig {
+    fn default() -> Self {
+        Self {
+            threads: 1,
+            pin_cores: false,
         }
     }
-    
+}
+
+/// Runs `f(0)..f(n)` across `config.threads` worker threads, collecting the
+/// results back into original index order regardless of which thread
+/// finishes first. Falls back to running inline when `threads <= 1` or there
+/// isn't enough work to split.
+fn run_partitioned<T, F>(n: usize, config: &ComputationProofConfig, f: F) -> Vec<T>
+where
+    F: Fn(usize) -> T + Send + Sync,
+    T: Send,
+{
+    let threads = config.threads.max(1).min(n.max(1));
+    if threads <= 1 {
+        return (0..n).map(f).collect();
+    }
+
+    let core_ids = if config.pin_cores {
+        core_affinity::get_core_ids()
+    } else {
+        None
+    };
+    let chunk_size = (n + threads - 1) / threads;
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let start = t * chunk_size;
+                let end = (start + chunk_size).min(n);
+                let pin_to = core_ids
+                    .as_ref()
+                    .and_then(|ids| ids.get(t % ids.len()))
+                    .copied();
+                let f = &f;
+                scope.spawn(move || {
+                    if let Some(core_id) = pin_to {
+                        // Best-effort: pinning can fail on some platforms
+                        // (e.g. sandboxed containers); a failure just leaves
+                        // this worker unpinned rather than aborting the proof.
+                        core_affinity::set_for_current(core_id);
+                    }
+                    (start..end).map(|i| f(i)).collect::<Vec<T>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("computation proof worker thread panicked"))
+            .collect()
+    })
+}
+
+impl ComputationProof {
     pub fn verify(&self, block_commitment: &[u8], spot_check_count: usize) -> bool {
-        use rand::Rng;
-        
-        // Randomly select nonces to verify
-        let mut rng = rand::thread_rng();
-        let nonces: Vec<u64> = (0..spot_check_count)
-            .map(|_| rng.gen_range(self.nonce_range.clone()))
+        self.verify_with_config(block_commitment, spot_check_count, &ComputationProofConfig::default())
+    }
+
+    /// Same as [`Self::verify`], but spreads the spot-check recomputation
+    /// across `config.threads` worker threads -- worthwhile when a proof has
+    /// enough distinct samples that spot-checks land on different leaves.
+    /// A single-leaf proof (what every live share validates as, see
+    /// `ShareValidator::validate_computation_proof`) has every challenge
+    /// resolve to the same leaf, so threading it would only pay OS
+    /// thread-spawn overhead inside the async validation path for zero
+    /// benefit; that case always runs inline regardless of `config`.
+    pub fn verify_with_config(
+        &self,
+        block_commitment: &[u8],
+        spot_check_count: usize,
+        config: &ComputationProofConfig,
+    ) -> bool {
+        let sample_count = self.leaves.len();
+        if sample_count == 0 || self.auth_paths.len() != sample_count {
+            return false;
+        }
+
+        let step = ((self.nonce_range.end - self.nonce_range.start) / sample_count as u64).max(1);
+
+        let challenges: Vec<(u64, usize)> = (0..spot_check_count as u64)
+            .map(|challenge_counter| {
+                let index = Self::fiat_shamir_index(
+                    &self.witness_commitment,
+                    block_commitment,
+                    challenge_counter,
+                    sample_count,
+                );
+                (challenge_counter, index)
+            })
             .collect();
-        
-        // Verify each selected nonce
-        for nonce in nonces {
+
+        let effective_config = if sample_count <= 1 {
+            ComputationProofConfig::default()
+        } else {
+            *config
+        };
+
+        let results = run_partitioned(challenges.len(), &effective_config, |i| {
+            let (challenge_counter, index) = challenges[i];
+            let nonce = self.nonce_range.start + index as u64 * step;
+            if nonce >= self.nonce_range.end {
+                debug!("Spot-checked nonce {} outside range {:?}", nonce, self.nonce_range);
+                return false;
+            }
+
             let witness = compute_partial_witness(block_commitment, nonce);
             let witness_bytes = witness_to_bytes(&witness);
-            let hash = Sha256::digest(&witness_bytes);
-            
-            // Check if hash matches any intermediate
-            let found = self.intermediate_hashes.iter().any(|h| {
-                // Allow some flexibility in matching due to sampling
-                h[..8] == hash[..8]
-            });
-            
-            if !found {
-                debug!("Failed to verify nonce {} in range {:?}", nonce, self.nonce_range);
+            let leaf: [u8; 32] = Sha256::digest(&witness_bytes).into();
+
+            if !verify_auth_path(leaf, index, &self.auth_paths[index], self.witness_commitment) {
+                debug!(
+                    "Merkle spot-check failed for nonce {} (leaf {}, challenge {}) in range {:?}",
+                    nonce, index, challenge_counter, self.nonce_range
+                );
                 return false;
             }
-        }
-        
-        true
+
+            true
+        });
+
+        results.into_iter().all(|ok| ok)
+    }
+
+    /// Derives the leaf index to spot-check for `challenge_counter` from
+    /// `witness_commitment || block_commitment || challenge_counter`, so the
+    /// set of samples checked is a deterministic function of the proof
+    /// itself -- a miner can't predict which samples will be checked before
+    /// committing to the root, and any verifier recomputes the same sequence.
+    fn fiat_shamir_index(
+        witness_commitment: &[u8; 32],
+        block_commitment: &[u8],
+        challenge_counter: u64,
+        sample_count: usize,
+    ) -> usize {
+        let mut hasher = Sha256::new();
+        hasher.update(witness_commitment);
+        hasher.update(block_commitment);
+        hasher.update(challenge_counter.to_le_bytes());
+        let digest = hasher.finalize();
+        let value = u64::from_le_bytes(digest[0..8].try_into().expect("digest is 32 bytes"));
+        (value % sample_count as u64) as usize
     }
 }
 
@@ -101,8 +202,25 @@ fn witness_to_bytes(witness: &NounSlab) -> Vec<u8> {
     bytes
 }
 
-fn estimate_computation_steps(witness_bytes: &[u8]) -> u64 {
-    // Estimate based on witness size
-    // In production, would track actual computation steps
-    witness_bytes.len() as u64 * 100
-}
\ No newline at end of file
+fn merkle_parent(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn verify_auth_path(leaf: [u8; 32], index: usize, path: &[[u8; 32]], root: [u8; 32]) -> bool {
+    let mut hash = leaf;
+    let mut idx = index;
+
+    for sibling in path {
+        hash = if idx % 2 == 0 {
+            merkle_parent(&hash, sibling)
+        } else {
+            merkle_parent(sibling, &hash)
+        };
+        idx /= 2;
+    }
+
+    hash == root
+}
@@ -2,6 +2,6 @@ pub mod computation_proof;
 pub mod validator;
 pub mod types;
 
-pub use computation_proof::ComputationProof;
+pub use computation_proof::{ComputationProof, ComputationProofConfig};
 pub use validator::ShareValidator;
 pub use types::{ShareSubmission, ShareType, ShareValidation};
\ No newline at end of file
@@ -10,13 +10,32 @@ pub struct ShareSubmission {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ShareType {
     ComputationProof {
+        /// The nonce actually verified against the job's `block_commitment`,
+        /// reconstructed by the Stratum layer from the connection's
+        /// `extranonce1` and the submitted `extranonce2` (see
+        /// `StratumServer::reconstruct_nonce`) rather than taken as-is from
+        /// the miner.
         nonce: u64,
         witness_commitment: [u8; 32],
         computation_steps: u64,
+        /// Version-rolling bits the miner searched in addition to `nonce`,
+        /// granted via `mining.configure` (see `MinerConnection::version_rolling_mask`).
+        #[serde(default)]
+        version_bits: u32,
+        /// `extranonce2` the miner rolled within its assigned `extranonce1`
+        /// prefix. Only meaningful on the wire; once the Stratum layer
+        /// reconstructs `nonce` from it, it's kept solely so the type can
+        /// round-trip.
+        #[serde(default)]
+        extranonce2: String,
     },
     ValidBlock {
         nonce: u64,
         proof: Vec<u8>,
+        #[serde(default)]
+        version_bits: u32,
+        #[serde(default)]
+        extranonce2: String,
     },
 }
 
@@ -6,7 +6,7 @@ use tracing::{debug, warn};
 use crate::database::{RedisStore, JobTemplate};
 use crate::error::{PoolError, Result};
 use super::types::{ShareSubmission, ShareType, ShareValidation};
-use super::computation_proof::ComputationProof;
+use super::computation_proof::{ComputationProof, ComputationProofConfig};
 
 const SPOT_CHECK_COUNT: usize = 5;
 const BLOCK_REWARD_UNITS: u64 = 1_000_000;
@@ -14,30 +14,48 @@ const BLOCK_REWARD_UNITS: u64 = 1_000_000;
 pub struct ShareValidator {
     redis: Arc<RwLock<RedisStore>>,
     validation_threshold: f64,
+    proof_config: ComputationProofConfig,
 }
 
 impl ShareValidator {
     pub fn new(redis: Arc<RwLock<RedisStore>>, validation_threshold: f64) -> Self {
+        Self::with_proof_config(redis, validation_threshold, ComputationProofConfig::default())
+    }
+
+    pub fn with_proof_config(
+        redis: Arc<RwLock<RedisStore>>,
+        validation_threshold: f64,
+        proof_config: ComputationProofConfig,
+    ) -> Self {
         Self {
             redis,
             validation_threshold,
+            proof_config,
         }
     }
-    
+
+    /// Validates `share`, weighting it against `assigned_difficulty` — the
+    /// difficulty the submitting connection's vardiff controller had in
+    /// force at the moment the share arrived. This keeps `reward_units`/
+    /// `total_difficulty` accounting fair across retargets: a share accepted
+    /// while a miner is assigned difficulty 1000 is worth 1000x a share
+    /// accepted while it was assigned difficulty 1, regardless of how far
+    /// past that bar the proof happened to land.
     pub async fn validate_share(
         &self,
         share: ShareSubmission,
+        assigned_difficulty: u64,
     ) -> Result<ShareValidation> {
         // Check if share is duplicate
         if self.is_duplicate(&share).await? {
             return Err(PoolError::DuplicateShare);
         }
-        
+
         match &share.share_type {
-            ShareType::ComputationProof { witness_commitment, computation_steps, nonce } => {
-                self.validate_computation_proof(&share.job_id, *witness_commitment, *computation_steps, *nonce).await
+            ShareType::ComputationProof { witness_commitment, computation_steps, nonce, .. } => {
+                self.validate_computation_proof(&share.job_id, *witness_commitment, *computation_steps, *nonce, assigned_difficulty).await
             }
-            ShareType::ValidBlock { proof, nonce } => {
+            ShareType::ValidBlock { proof, nonce, .. } => {
                 self.validate_block(&share.job_id, proof, *nonce).await
             }
         }
@@ -53,10 +71,13 @@ impl ShareValidator {
                 format!("{}:{}:{}", share.job_id, share.miner_id, nonce)
             }
         };
-        
-        // Check in Redis if this share ID already exists
-        // This is simplified - in production would use proper duplicate detection
-        Ok(false)
+
+        // `mark_share_seen` records the id with a single round-trip SETNX,
+        // so a replay racing the original submission still can't slip
+        // through between a separate exists-check and write.
+        let mut redis = self.redis.write().await;
+        let first_seen = redis.mark_share_seen(&share_id).await?;
+        Ok(!first_seen)
     }
     
     async fn validate_computation_proof(
@@ -65,31 +86,40 @@ impl ShareValidator {
         witness_commitment: [u8; 32],
         computation_steps: u64,
         nonce: u64,
+        assigned_difficulty: u64,
     ) -> Result<ShareValidation> {
         // Get job template
         let job = self.get_job_template(job_id).await?;
-        
-        // Create a computation proof for verification
+
+        // Create a computation proof for verification. A live share only
+        // submits one sample, so it's a degenerate single-leaf Merkle tree:
+        // the leaf is the root and the auth path is empty.
         let proof = ComputationProof {
             witness_commitment,
             nonce_range: nonce..nonce + 1,
             computation_steps,
-            intermediate_hashes: vec![witness_commitment],
+            leaves: vec![witness_commitment],
+            auth_paths: vec![Vec::new()],
         };
-        
+
         // Verify proof
-        if !proof.verify(&job.block_commitment, SPOT_CHECK_COUNT) {
+        if !proof.verify_with_config(&job.block_commitment, SPOT_CHECK_COUNT, &self.proof_config) {
             return Err(PoolError::InvalidProof);
         }
-        
-        // Calculate share difficulty
-        let difficulty = self.calculate_share_difficulty(&witness_commitment);
-        
+
+        // The proof must actually clear the difficulty the connection was
+        // assigned when it submitted, or vardiff's per-connection target is
+        // meaningless.
+        let proof_difficulty = self.calculate_share_difficulty(&witness_commitment);
+        if proof_difficulty < assigned_difficulty {
+            return Err(PoolError::InsufficientDifficulty);
+        }
+
         Ok(ShareValidation {
             is_valid: true,
-            difficulty,
+            difficulty: assigned_difficulty,
             is_block: false,
-            reward_units: difficulty * computation_steps,
+            reward_units: assigned_difficulty * computation_steps,
         })
     }
     
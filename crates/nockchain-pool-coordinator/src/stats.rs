@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use crate::error::PoolError;
+use crate::metrics::{WORKER_HASHRATE, WORKER_SHARES_ACCEPTED, WORKER_SHARES_REJECTED};
+
+/// How much accepted-share history each worker keeps for hashrate estimation.
+const DIFFICULTY_HISTORY_LEN: usize = 256;
+
+#[derive(Debug, Clone, Default)]
+struct WorkerStats {
+    accepted_shares: u64,
+    rejected_duplicate: u64,
+    rejected_invalid_proof: u64,
+    rejected_insufficient_difficulty: u64,
+    rejected_other: u64,
+    stale_shares: u64,
+    last_share_time: Option<DateTime<Utc>>,
+    // (timestamp, difficulty) of recent accepted shares, oldest first.
+    accepted_difficulty_window: VecDeque<(DateTime<Utc>, u64)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerStatsSnapshot {
+    pub miner_address: String,
+    pub accepted_shares: u64,
+    pub rejected_duplicate: u64,
+    pub rejected_invalid_proof: u64,
+    pub rejected_insufficient_difficulty: u64,
+    pub rejected_other: u64,
+    pub stale_shares: u64,
+    pub last_share_time: Option<DateTime<Utc>>,
+    pub estimated_hashrate: f64,
+}
+
+/// Aggregates per-worker and pool-wide share counters for operator
+/// monitoring, separate from the payout accounting in `payout`/`database`.
+pub struct StatsTracker {
+    workers: RwLock<HashMap<String, WorkerStats>>,
+    hashrate_window_secs: i64,
+    /// Hashrate miners self-report (e.g. via the `eth_submitHashrate`-style
+    /// JSON-RPC call), kept separate from `estimate_hashrate`'s
+    /// accepted-share-derived figure since a self-reported number isn't
+    /// independently verified.
+    self_reported_hashrate: RwLock<HashMap<String, f64>>,
+}
+
+impl StatsTracker {
+    pub fn new(hashrate_window_secs: i64) -> Self {
+        Self {
+            workers: RwLock::new(HashMap::new()),
+            hashrate_window_secs,
+            self_reported_hashrate: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn record_self_reported_hashrate(&self, miner_address: &str, hashrate: f64) {
+        self.self_reported_hashrate
+            .write()
+            .await
+            .insert(miner_address.to_string(), hashrate);
+    }
+
+    pub async fn self_reported_hashrate(&self, miner_address: &str) -> Option<f64> {
+        self.self_reported_hashrate.read().await.get(miner_address).copied()
+    }
+
+    pub async fn record_accepted(&self, miner_address: &str, difficulty: u64) {
+        let now = Utc::now();
+        let mut workers = self.workers.write().await;
+        let stats = workers.entry(miner_address.to_string()).or_default();
+
+        stats.accepted_shares += 1;
+        stats.last_share_time = Some(now);
+        stats.accepted_difficulty_window.push_back((now, difficulty));
+        while stats.accepted_difficulty_window.len() > DIFFICULTY_HISTORY_LEN {
+            stats.accepted_difficulty_window.pop_front();
+        }
+
+        WORKER_SHARES_ACCEPTED.with_label_values(&[miner_address]).inc();
+        WORKER_HASHRATE
+            .with_label_values(&[miner_address])
+            .set(Self::estimate_hashrate(stats, self.hashrate_window_secs));
+    }
+
+    pub async fn record_rejected(&self, miner_address: &str, error: &PoolError) {
+        let mut workers = self.workers.write().await;
+        let stats = workers.entry(miner_address.to_string()).or_default();
+
+        let reason = match error {
+            PoolError::DuplicateShare => {
+                stats.rejected_duplicate += 1;
+                "duplicate"
+            }
+            PoolError::InvalidProof => {
+                stats.rejected_invalid_proof += 1;
+                "invalid_proof"
+            }
+            PoolError::InsufficientDifficulty => {
+                stats.rejected_insufficient_difficulty += 1;
+                "insufficient_difficulty"
+            }
+            _ => {
+                stats.rejected_other += 1;
+                "other"
+            }
+        };
+
+        WORKER_SHARES_REJECTED.with_label_values(&[miner_address, reason]).inc();
+    }
+
+    pub async fn record_stale(&self, miner_address: &str) {
+        let mut workers = self.workers.write().await;
+        workers.entry(miner_address.to_string()).or_default().stale_shares += 1;
+    }
+
+    /// Zeroes a disconnected worker's exported hashrate gauge immediately,
+    /// rather than leaving it at its last nonzero value until the rolling
+    /// window it was computed from empties out on its own.
+    pub async fn mark_disconnected(&self, miner_address: &str) {
+        WORKER_HASHRATE.with_label_values(&[miner_address]).set(0.0);
+    }
+
+    pub async fn worker_snapshot(&self, miner_address: &str) -> Option<WorkerStatsSnapshot> {
+        let workers = self.workers.read().await;
+        workers.get(miner_address).map(|stats| {
+            Self::to_snapshot(miner_address.to_string(), stats, self.hashrate_window_secs)
+        })
+    }
+
+    pub async fn all_snapshots(&self) -> Vec<WorkerStatsSnapshot> {
+        let workers = self.workers.read().await;
+        workers
+            .iter()
+            .map(|(address, stats)| Self::to_snapshot(address.clone(), stats, self.hashrate_window_secs))
+            .collect()
+    }
+
+    /// Pool-wide hashrate, rolled up from every worker's own rolling estimate
+    /// rather than derived separately from a Redis share-window sum.
+    pub async fn total_hashrate(&self) -> f64 {
+        let workers = self.workers.read().await;
+        workers
+            .values()
+            .map(|stats| Self::estimate_hashrate(stats, self.hashrate_window_secs))
+            .sum()
+    }
+
+    fn estimate_hashrate(stats: &WorkerStats, window_secs: i64) -> f64 {
+        let now = Utc::now();
+        let cutoff = now - chrono::Duration::seconds(window_secs);
+        let total_difficulty: u64 = stats
+            .accepted_difficulty_window
+            .iter()
+            .filter(|(ts, _)| *ts >= cutoff)
+            .map(|(_, difficulty)| *difficulty)
+            .sum();
+
+        if total_difficulty == 0 {
+            return 0.0;
+        }
+
+        // hashrate ~= sum(share_difficulty) * 2^32 / window_seconds
+        (total_difficulty as f64) * 2f64.powi(32) / window_secs as f64
+    }
+
+    fn to_snapshot(miner_address: String, stats: &WorkerStats, window_secs: i64) -> WorkerStatsSnapshot {
+        WorkerStatsSnapshot {
+            miner_address,
+            accepted_shares: stats.accepted_shares,
+            rejected_duplicate: stats.rejected_duplicate,
+            rejected_invalid_proof: stats.rejected_invalid_proof,
+            rejected_insufficient_difficulty: stats.rejected_insufficient_difficulty,
+            rejected_other: stats.rejected_other,
+            stale_shares: stats.stale_shares,
+            last_share_time: stats.last_share_time,
+            estimated_hashrate: Self::estimate_hashrate(stats, window_secs),
+        }
+    }
+}
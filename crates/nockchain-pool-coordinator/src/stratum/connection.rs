@@ -1,96 +1,249 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::net::SocketAddr;
+use std::collections::VecDeque;
 use tokio::sync::{mpsc, Mutex};
-use axum::extract::ws::{WebSocket, Message};
-use futures::{SinkExt, StreamExt};
-use tracing::{debug, error, info};
-use crate::error::Result;
+use axum::extract::ws::WebSocket;
+use chrono::{DateTime, Utc};
+use tracing::{error, info};
+use crate::error::{PoolError, Result};
+use super::transport::{Frame, Transport, WsJsonTransport};
+
+/// What a `MinerConnection::send_frame`/`close` call enqueues for the
+/// connection's transport loop to act on.
+enum OutboundMessage {
+    Frame(Frame),
+    Close,
+}
+
+/// Difficulty assigned to a connection before the vardiff controller has
+/// retargeted it for the first time.
+pub const DEFAULT_DIFFICULTY: u64 = 1;
+
+/// How many recent accepted-share timestamps we keep per connection for
+/// vardiff rate estimation.
+const SHARE_HISTORY_LEN: usize = 32;
 
 pub struct MinerConnection {
     pub id: String,
     pub address: SocketAddr,
-    pub worker_name: Option<String>,
-    pub authorized: bool,
-    sender: mpsc::Sender<Message>,
+    /// Worker name assigned by `mining.authorize`, behind a `Mutex` like
+    /// `recent_share_times` because `MinerConnection` is always reached
+    /// through a shared `Arc` (the connection loop, `active_connections`,
+    /// and the job forwarder each hold a clone), so `Arc::get_mut` is never
+    /// available to mutate it in place.
+    worker_name: Mutex<Option<String>>,
+    authorized: AtomicBool,
+    sender: mpsc::Sender<OutboundMessage>,
+    difficulty: AtomicU64,
+    /// Version-rolling mask granted to this connection via `mining.configure`,
+    /// or `0` if it never negotiated (or wasn't granted) the extension.
+    version_rolling_mask: AtomicU32,
+    /// Floor requested via the `minimum-difficulty` extension; the vardiff
+    /// retargeter won't set this connection's difficulty below it. `0` if
+    /// the miner never negotiated the extension.
+    minimum_difficulty: AtomicU64,
+    /// Set once this connection negotiates the `subscribe-extranonce`
+    /// extension via `mining.configure`.
+    subscribed_extranonce: AtomicBool,
+    /// Extranonce1 prefix assigned to this connection at construction, so
+    /// every connection searches a disjoint slice of the extranonce space
+    /// and `mining.submit` can reconstruct the nonce it actually covers.
+    extranonce1: String,
+    recent_share_times: Mutex<VecDeque<DateTime<Utc>>>,
+}
+
+/// Derives a stable per-connection `extranonce1` from the connection id, the
+/// same way `JobTemplate::calculate_nonce_range` derives a disjoint nonce
+/// range from a miner id.
+fn generate_extranonce1(connection_id: &str) -> String {
+    let digest = sha2::Sha256::digest(connection_id.as_bytes());
+    hex::encode(&digest[..4])
 }
 
 impl MinerConnection {
-    pub fn new(id: String, address: SocketAddr, sender: mpsc::Sender<Message>) -> Self {
+    fn new(id: String, address: SocketAddr, sender: mpsc::Sender<OutboundMessage>) -> Self {
+        let extranonce1 = generate_extranonce1(&id);
         Self {
             id,
             address,
-            worker_name: None,
-            authorized: false,
+            worker_name: Mutex::new(None),
+            authorized: AtomicBool::new(false),
             sender,
+            difficulty: AtomicU64::new(DEFAULT_DIFFICULTY),
+            version_rolling_mask: AtomicU32::new(0),
+            minimum_difficulty: AtomicU64::new(0),
+            subscribed_extranonce: AtomicBool::new(false),
+            extranonce1,
+            recent_share_times: Mutex::new(VecDeque::with_capacity(SHARE_HISTORY_LEN)),
         }
     }
-    
-    pub async fn send_message(&self, message: String) -> Result<()> {
-        self.sender.send(Message::Text(message)).await
-            .map_err(|_| crate::error::PoolError::WebSocket("Failed to send message".to_string()))?;
+
+    pub async fn send_frame(&self, frame: Frame) -> Result<()> {
+        self.sender.send(OutboundMessage::Frame(frame)).await
+            .map_err(|_| PoolError::WebSocket("Failed to send frame".to_string()))?;
         Ok(())
     }
-    
+
     pub async fn close(&self) -> Result<()> {
-        self.sender.send(Message::Close(None)).await
-            .map_err(|_| crate::error::PoolError::WebSocket("Failed to send close".to_string()))?;
+        self.sender.send(OutboundMessage::Close).await
+            .map_err(|_| PoolError::WebSocket("Failed to send close".to_string()))?;
         Ok(())
     }
+
+    /// Worker name assigned by `mining.authorize`, or `None` before that
+    /// handshake completes.
+    pub async fn worker_name(&self) -> Option<String> {
+        self.worker_name.lock().await.clone()
+    }
+
+    pub async fn set_worker_name(&self, worker_name: String) {
+        *self.worker_name.lock().await = Some(worker_name);
+    }
+
+    /// Whether this connection has completed `mining.authorize`.
+    pub fn authorized(&self) -> bool {
+        self.authorized.load(Ordering::Relaxed)
+    }
+
+    pub fn set_authorized(&self, authorized: bool) {
+        self.authorized.store(authorized, Ordering::Relaxed);
+    }
+
+    /// Difficulty currently required of shares submitted on this connection.
+    pub fn current_difficulty(&self) -> u64 {
+        self.difficulty.load(Ordering::Relaxed)
+    }
+
+    pub fn set_difficulty(&self, difficulty: u64) {
+        self.difficulty.store(difficulty, Ordering::Relaxed);
+    }
+
+    /// Version-rolling mask granted to this connection, or `0` if none.
+    pub fn version_rolling_mask(&self) -> u32 {
+        self.version_rolling_mask.load(Ordering::Relaxed)
+    }
+
+    pub fn set_version_rolling_mask(&self, mask: u32) {
+        self.version_rolling_mask.store(mask, Ordering::Relaxed);
+    }
+
+    /// Difficulty floor negotiated via the `minimum-difficulty` extension,
+    /// or `0` if none was requested.
+    pub fn minimum_difficulty(&self) -> u64 {
+        self.minimum_difficulty.load(Ordering::Relaxed)
+    }
+
+    pub fn set_minimum_difficulty(&self, minimum: u64) {
+        self.minimum_difficulty.store(minimum, Ordering::Relaxed);
+    }
+
+    /// Whether this connection negotiated `subscribe-extranonce`.
+    pub fn subscribed_extranonce(&self) -> bool {
+        self.subscribed_extranonce.load(Ordering::Relaxed)
+    }
+
+    pub fn set_subscribed_extranonce(&self, subscribed: bool) {
+        self.subscribed_extranonce.store(subscribed, Ordering::Relaxed);
+    }
+
+    /// Extranonce1 prefix assigned to this connection, returned in its
+    /// `mining.subscribe` response.
+    pub fn extranonce1(&self) -> &str {
+        &self.extranonce1
+    }
+
+    /// Record an accepted share's arrival time, trimming to the retained history.
+    pub async fn record_accepted_share(&self) {
+        let mut times = self.recent_share_times.lock().await;
+        times.push_back(Utc::now());
+        while times.len() > SHARE_HISTORY_LEN {
+            times.pop_front();
+        }
+    }
+
+    /// Observed shares-per-minute over the retained history, or `None` if
+    /// there isn't enough history yet to estimate a rate.
+    pub async fn observed_shares_per_minute(&self) -> Option<f64> {
+        let times = self.recent_share_times.lock().await;
+        if times.len() < 2 {
+            return None;
+        }
+        let oldest = *times.front().expect("checked len >= 2");
+        let elapsed_secs = (Utc::now() - oldest).num_milliseconds() as f64 / 1000.0;
+        if elapsed_secs <= 0.0 {
+            return None;
+        }
+        Some((times.len() - 1) as f64 / elapsed_secs * 60.0)
+    }
 }
 
-pub async fn handle_websocket(
-    ws: WebSocket,
+/// Drives a connection of any `Transport` through the same connect/message/
+/// disconnect lifecycle, so a plaintext WebSocket miner and a Noise-encrypted
+/// binary one (the SV2 listener) are handled identically above this point.
+pub async fn handle_connection(
+    mut transport: impl Transport,
     addr: SocketAddr,
     miner_id: String,
     handler: Arc<dyn ConnectionHandler + Send + Sync>,
 ) {
-    let (ws_sender, mut ws_receiver) = ws.split();
     let (tx, mut rx) = mpsc::channel(100);
-    
     let connection = Arc::new(MinerConnection::new(miner_id.clone(), addr, tx));
-    
-    // Spawn task to forward messages from channel to websocket
-    let ws_sender = Arc::new(Mutex::new(ws_sender));
-    let ws_sender_clone = ws_sender.clone();
-    tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if let Err(e) = ws_sender_clone.lock().await.send(msg).await {
-                error!("WebSocket send error: {}", e);
-                break;
-            }
-        }
-    });
-    
-    // Handle connection
+
     handler.on_connect(connection.clone()).await;
-    
-    // Process incoming messages
-    while let Some(msg) = ws_receiver.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                if let Err(e) = handler.on_message(connection.clone(), text).await {
-                    error!("Error handling message: {}", e);
+
+    loop {
+        tokio::select! {
+            incoming = transport.recv() => {
+                match incoming {
+                    Ok(Some(frame)) => {
+                        if let Err(e) = handler.on_message(connection.clone(), frame).await {
+                            error!("Error handling message: {}", e);
+                        }
+                    }
+                    Ok(None) => {
+                        info!("Client {} closed connection", miner_id);
+                        break;
+                    }
+                    Err(e) => {
+                        error!("Transport error: {}", e);
+                        break;
+                    }
                 }
             }
-            Ok(Message::Close(_)) => {
-                info!("Client {} closed connection", miner_id);
-                break;
-            }
-            Err(e) => {
-                error!("WebSocket error: {}", e);
-                break;
+
+            outbound = rx.recv() => {
+                match outbound {
+                    Some(OutboundMessage::Frame(frame)) => {
+                        if let Err(e) = transport.send(frame).await {
+                            error!("Transport send error: {}", e);
+                            break;
+                        }
+                    }
+                    Some(OutboundMessage::Close) | None => {
+                        let _ = transport.close().await;
+                        break;
+                    }
+                }
             }
-            _ => {}
         }
     }
-    
-    // Handle disconnection
+
     handler.on_disconnect(connection.clone()).await;
 }
 
+pub async fn handle_websocket(
+    ws: WebSocket,
+    addr: SocketAddr,
+    miner_id: String,
+    handler: Arc<dyn ConnectionHandler + Send + Sync>,
+) {
+    handle_connection(WsJsonTransport::new(ws), addr, miner_id, handler).await
+}
+
 #[async_trait::async_trait]
 pub trait ConnectionHandler {
     async fn on_connect(&self, connection: Arc<MinerConnection>);
-    async fn on_message(&self, connection: Arc<MinerConnection>, message: String) -> Result<()>;
+    async fn on_message(&self, connection: Arc<MinerConnection>, frame: Frame) -> Result<()>;
     async fn on_disconnect(&self, connection: Arc<MinerConnection>);
 }
\ No newline at end of file
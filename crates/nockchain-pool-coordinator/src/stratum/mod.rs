@@ -1,6 +1,8 @@
 pub mod server;
 pub mod protocol;
 pub mod connection;
+pub mod transport;
 
-pub use server::StratumServer;
-pub use protocol::{StratumMessage, StratumRequest, StratumResponse};
\ No newline at end of file
+pub use server::{StratumServer, JobDispatcher, JobNotification};
+pub use protocol::{StratumMessage, StratumRequest, StratumResponse};
+pub use transport::{Frame, NoiseTransport, Transport, WsJsonTransport};
\ No newline at end of file
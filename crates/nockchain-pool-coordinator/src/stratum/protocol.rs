@@ -33,11 +33,20 @@ pub enum StratumRequest {
         worker_name: String,
         password: Option<String>,
     },
+    /// `mining.configure`: the miner advertises extensions it wants to use.
+    /// `version-rolling`, `minimum-difficulty`, and `subscribe-extranonce`
+    /// are understood; anything else is silently left ungranted.
+    Configure {
+        id: u64,
+        version_rolling_mask: Option<u32>,
+        version_rolling_min_bit_count: Option<u32>,
+        minimum_difficulty: Option<u64>,
+        subscribe_extranonce: bool,
+    },
     Submit {
         id: u64,
         worker_name: String,
         job_id: String,
-        nonce: u64,
         share_data: ShareSubmissionData,
     },
     GetStatus {
@@ -50,9 +59,20 @@ pub enum ShareSubmissionData {
     ComputationProof {
         witness_commitment: [u8; 32],
         computation_steps: u64,
+        #[serde(default)]
+        version_bits: u32,
+        /// Raw nonce and extranonce2 as submitted, before
+        /// `StratumServer::reconstruct_nonce` folds them with the
+        /// connection's `extranonce1` into the nonce actually verified.
+        nonce: u64,
+        extranonce2: String,
     },
     ValidBlock {
         proof: Vec<u8>,
+        #[serde(default)]
+        version_bits: u32,
+        nonce: u64,
+        extranonce2: String,
     },
 }
 
@@ -121,6 +141,57 @@ impl StratumMessage {
                 
                 Ok(StratumRequest::Authorize { id, worker_name, password })
             }
+            "mining.configure" => {
+                let params = self.params.as_ref()
+                    .and_then(|p| p.as_array())
+                    .ok_or_else(|| StratumError {
+                        code: -32602,
+                        message: "Invalid params".to_string(),
+                        data: None,
+                    })?;
+
+                let extensions = params.get(0)
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                let extension_params = params.get(1);
+
+                let version_rolling_requested = extensions.iter()
+                    .any(|e| e.as_str() == Some("version-rolling"));
+
+                let (version_rolling_mask, version_rolling_min_bit_count) = if version_rolling_requested {
+                    let mask = extension_params
+                        .and_then(|p| p.get("version-rolling.mask"))
+                        .and_then(|v| v.as_str())
+                        .and_then(|s| u32::from_str_radix(s, 16).ok());
+                    let min_bit_count = extension_params
+                        .and_then(|p| p.get("version-rolling.min-bit-count"))
+                        .and_then(|v| v.as_u64())
+                        .map(|n| n as u32);
+                    (mask, min_bit_count)
+                } else {
+                    (None, None)
+                };
+
+                let minimum_difficulty = if extensions.iter().any(|e| e.as_str() == Some("minimum-difficulty")) {
+                    extension_params
+                        .and_then(|p| p.get("minimum-difficulty.value"))
+                        .and_then(|v| v.as_u64())
+                } else {
+                    None
+                };
+
+                let subscribe_extranonce = extensions.iter()
+                    .any(|e| e.as_str() == Some("subscribe-extranonce"));
+
+                Ok(StratumRequest::Configure {
+                    id,
+                    version_rolling_mask,
+                    version_rolling_min_bit_count,
+                    minimum_difficulty,
+                    subscribe_extranonce,
+                })
+            }
             "mining.submit" => {
                 let params = self.params.as_ref()
                     .ok_or_else(|| StratumError {
@@ -141,13 +212,12 @@ impl StratumMessage {
                     id,
                     worker_name: submission.miner_id.clone(),
                     job_id: submission.job_id,
-                    nonce: 0, // Will be extracted from share_data
                     share_data: match submission.share_type {
-                        crate::shares::ShareType::ComputationProof { witness_commitment, computation_steps, .. } => {
-                            ShareSubmissionData::ComputationProof { witness_commitment, computation_steps }
+                        crate::shares::ShareType::ComputationProof { witness_commitment, computation_steps, version_bits, nonce, extranonce2 } => {
+                            ShareSubmissionData::ComputationProof { witness_commitment, computation_steps, version_bits, nonce, extranonce2 }
                         }
-                        crate::shares::ShareType::ValidBlock { proof, .. } => {
-                            ShareSubmissionData::ValidBlock { proof }
+                        crate::shares::ShareType::ValidBlock { proof, version_bits, nonce, extranonce2 } => {
+                            ShareSubmissionData::ValidBlock { proof, version_bits, nonce, extranonce2 }
                         }
                     },
                 })
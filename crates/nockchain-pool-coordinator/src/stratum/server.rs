@@ -1,6 +1,7 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::net::SocketAddr;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use axum::{
     extract::{ws::WebSocketUpgrade, State, ConnectInfo},
     response::Response,
@@ -8,6 +9,7 @@ use axum::{
     Router,
     Json,
 };
+use tokio::net::TcpListener;
 use tokio::sync::{broadcast, RwLock};
 use uuid::Uuid;
 use tracing::{info, warn, debug, error};
@@ -16,24 +18,106 @@ use serde_json::json;
 use crate::coordinator::PoolCoordinator;
 use crate::database::{JobTemplate, PoolStats};
 use crate::error::{PoolError, Result};
-use super::protocol::{StratumMessage, StratumRequest, StratumResponse, StratumError};
-use super::connection::{MinerConnection, handle_websocket, ConnectionHandler};
+use super::protocol::{StratumRequest, StratumResponse, StratumError};
+use super::connection::{MinerConnection, handle_connection, handle_websocket, ConnectionHandler};
+use super::transport::{Frame, NoiseTransport};
+
+/// How many of the most recent job ids are still accepted as "current" — lets
+/// a share that was in flight when a new job landed still be honored briefly.
+/// A job dispatched with `clean_jobs` retires every earlier job at once, so
+/// this grace only applies across non-clean dispatches.
+const MAX_OUTSTANDING_JOBS: usize = 8;
+
+/// How many ids we keep after they fall out of `outstanding_jobs`, purely to
+/// tell a genuinely unknown `job_id` (`PoolError::JobNotFound`) apart from one
+/// that was valid but has since been superseded (`PoolError::StaleShare`).
+const RETIRED_JOBS_LEN: usize = 64;
+
+/// Version bits this pool allows miners to roll, mirroring BIP320's general
+/// purpose range. A `mining.configure` request is granted the intersection
+/// of its requested mask with this one.
+const SUPPORTED_VERSION_ROLLING_MASK: u32 = 0x1fffe000;
+
+/// Bytes of `extranonce2` a miner is expected to roll itself, on top of the
+/// `extranonce1` prefix `MinerConnection` assigns it at connect time.
+const EXTRANONCE2_SIZE: u32 = 4;
+
+/// A job broadcast to every connected miner, carrying the clean-jobs flag
+/// that tells them whether to discard in-flight work.
+#[derive(Debug, Clone)]
+pub struct JobNotification {
+    pub job: JobTemplate,
+    pub clean_jobs: bool,
+}
+
+impl JobNotification {
+    /// Renders this job for one specific miner, with `nonce_start`/
+    /// `nonce_range` narrowed to the slice `calculate_nonce_range` assigned
+    /// it, so miners sharing a job don't redundantly search the same space.
+    fn to_frame(&self, notify_id: u64, nonce_start: u64, nonce_range: u64) -> Frame {
+        StratumResponse::Notification {
+            method: "mining.notify".to_string(),
+            params: json!({
+                "notify_id": notify_id,
+                "job_id": self.job.id,
+                "block_commitment": hex::encode(&self.job.block_commitment),
+                "target": hex::encode(&self.job.target),
+                "share_target": hex::encode(&self.job.share_target),
+                "nonce_start": nonce_start,
+                "nonce_range": nonce_range,
+                "clean_jobs": self.clean_jobs,
+            }),
+        }
+        .to_message()
+    }
+}
+
+/// Single choke point for pushing work to connected miners. Implemented by
+/// `StratumServer` so the coordinator (or anything else that learns of a new
+/// chain tip) has one abstraction to broadcast through instead of reaching
+/// into connection internals.
+#[async_trait::async_trait]
+pub trait JobDispatcher: Send + Sync {
+    /// Builds a fresh job template for a new chain tip at `height`, ready to
+    /// hand to `dispatch_job`.
+    fn payload(&self, height: u64, block_commitment: Vec<u8>, target: Vec<u8>, share_target: Vec<u8>, previous_block: String) -> JobTemplate {
+        JobTemplate {
+            id: Uuid::new_v4().to_string(),
+            block_commitment,
+            target,
+            share_target,
+            timestamp: chrono::Utc::now(),
+            nonce_ranges: HashMap::new(),
+            height,
+            previous_block,
+        }
+    }
+
+    async fn dispatch_job(&self, job: JobTemplate, clean_jobs: bool);
+    async fn push_difficulty(&self, connection: &Arc<MinerConnection>, difficulty: u64) -> Result<()>;
+}
 
 #[derive(Clone)]
 pub struct StratumServer {
     coordinator: Arc<PoolCoordinator>,
-    job_broadcaster: broadcast::Sender<JobTemplate>,
+    job_broadcaster: broadcast::Sender<JobNotification>,
     active_connections: Arc<RwLock<HashMap<String, Arc<MinerConnection>>>>,
+    outstanding_jobs: Arc<RwLock<VecDeque<String>>>,
+    retired_jobs: Arc<RwLock<VecDeque<String>>>,
+    notify_counter: Arc<AtomicU64>,
 }
 
 impl StratumServer {
     pub async fn new(coordinator: Arc<PoolCoordinator>) -> Self {
         let (job_broadcaster, _) = broadcast::channel(1024);
-        
+
         Self {
             coordinator,
             job_broadcaster,
             active_connections: Arc::new(RwLock::new(HashMap::new())),
+            outstanding_jobs: Arc::new(RwLock::new(VecDeque::new())),
+            retired_jobs: Arc::new(RwLock::new(VecDeque::new())),
+            notify_counter: Arc::new(AtomicU64::new(0)),
         }
     }
 
@@ -42,37 +126,183 @@ impl StratumServer {
             .route("/", get(websocket_handler))
             .route("/stats", get(stats_handler))
             .route("/api/stats/:address", get(miner_stats_handler))
+            .route("/api/workers", get(workers_handler))
+            .route("/api/workers/:address", get(worker_handler))
             .with_state(Arc::new(self))
     }
-    
+
+    /// Binds `bind_addr` and runs the SV2-style TCP listener, accepting raw
+    /// connections and driving them through the same `handle_connection`
+    /// lifecycle as WebSocket miners -- just speaking `NoiseTransport`'s
+    /// encrypted binary framing instead of `WsJsonTransport`'s JSON text.
+    /// Returns only if binding fails; accept errors are logged and the loop
+    /// keeps going. Callers spawn this on its own task.
+    pub async fn run_sv2_listener(self, bind_addr: SocketAddr) -> Result<()> {
+        let listener = TcpListener::bind(bind_addr)
+            .await
+            .map_err(|e| PoolError::WebSocket(e.to_string()))?;
+        info!("SV2 Stratum listener bound to {}", bind_addr);
+
+        let server = Arc::new(self);
+        loop {
+            let (stream, addr) = match listener.accept().await {
+                Ok(pair) => pair,
+                Err(e) => {
+                    error!("SV2 listener accept error: {}", e);
+                    continue;
+                }
+            };
+
+            let server = server.clone();
+            tokio::spawn(async move {
+                let transport = match NoiseTransport::handshake_responder(stream).await {
+                    Ok(transport) => transport,
+                    Err(e) => {
+                        warn!("SV2 handshake with {} failed: {}", addr, e);
+                        return;
+                    }
+                };
+
+                let miner_id = Uuid::new_v4().to_string();
+                handle_connection(transport, addr, miner_id, server).await;
+            });
+        }
+    }
+
+    /// Broadcast a brand-new job and tell miners to discard in-flight work.
     pub async fn broadcast_new_job(&self, job: JobTemplate) {
-        // Send to broadcaster
-        let _ = self.job_broadcaster.send(job.clone());
-        
-        // Also send directly to all connected miners
-        let connections = self.active_connections.read().await;
-        let notification = StratumResponse::Notification {
-            method: "mining.notify".to_string(),
-            params: json!({
-                "job_id": job.id,
-                "block_commitment": hex::encode(&job.block_commitment),
-                "target": hex::encode(&job.target),
-                "share_target": hex::encode(&job.share_target),
-                "clean_jobs": true,
-            }),
-        };
-        
-        let message = serde_json::to_string(&notification.to_message()).unwrap();
-        
-        for (miner_id, connection) in connections.iter() {
-            if connection.authorized {
-                if let Err(e) = connection.send_message(message.clone()).await {
-                    warn!("Failed to send job to miner {}: {}", miner_id, e);
+        self.dispatch_job(job, true).await;
+    }
+
+    /// Subscribe a freshly connected miner to the job broadcast channel so it
+    /// receives every `mining.notify` broadcast from here on, once authorized.
+    /// Difficulty changes don't go through this channel; `push_difficulty`
+    /// sends them to the one connection directly.
+    fn spawn_job_forwarder(&self, connection: Arc<MinerConnection>) {
+        let mut job_rx = self.job_broadcaster.subscribe();
+        let server = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match job_rx.recv().await {
+                    Ok(notification) => {
+                        if !connection.authorized() {
+                            continue;
+                        }
+                        let frame = server.render_job_for(&notification, &connection).await;
+                        if connection.send_frame(frame).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
                 }
             }
+        });
+    }
+
+    /// Renders `notification` for `connection`, narrowing the job's nonce
+    /// range to the slice `JobTemplate::calculate_nonce_range` assigns it out
+    /// of the currently connected miners, so a job broadcast to everyone
+    /// doesn't have every miner searching the same space.
+    async fn render_job_for(&self, notification: &JobNotification, connection: &Arc<MinerConnection>) -> Frame {
+        let total_miners = self.active_connections.read().await.len().max(1);
+        let range = notification.job.calculate_nonce_range(&connection.id, total_miners);
+        let notify_id = self.notify_counter.fetch_add(1, Ordering::Relaxed);
+        notification.to_frame(notify_id, range.start, range.end.saturating_sub(range.start))
+    }
+
+    async fn is_job_current(&self, job_id: &str) -> bool {
+        self.outstanding_jobs.read().await.iter().any(|id| id == job_id)
+    }
+
+    /// `true` if `job_id` was once current but has since been superseded by a
+    /// newer job, as opposed to never having existed at all.
+    async fn is_job_stale(&self, job_id: &str) -> bool {
+        self.retired_jobs.read().await.iter().any(|id| id == job_id)
+    }
+
+    /// Disconnects authorized workers that haven't had an accepted share in
+    /// over `idle_timeout_secs`. A worker that hasn't submitted its first
+    /// share yet is left alone — only established workers going idle count.
+    /// Returns the number of connections closed.
+    pub async fn disconnect_idle_workers(&self, idle_timeout_secs: i64) -> u64 {
+        let now = chrono::Utc::now();
+        let connections: Vec<_> = self.active_connections.read().await.values().cloned().collect();
+
+        let mut disconnected = 0;
+        for connection in connections {
+            let Some(worker_name) = connection.worker_name().await else {
+                continue;
+            };
+            let Some(snapshot) = self.coordinator.stats().worker_snapshot(&worker_name).await else {
+                continue;
+            };
+            let Some(last_share_time) = snapshot.last_share_time else {
+                continue;
+            };
+
+            let idle_secs = (now - last_share_time).num_seconds();
+            if idle_secs > idle_timeout_secs {
+                warn!("Disconnecting idle worker {} (no shares for {}s)", worker_name, idle_secs);
+                let _ = connection.close().await;
+                disconnected += 1;
+            }
         }
+
+        disconnected
     }
-    
+
+    /// Per-connection vardiff retargeting. Called after every accepted share;
+    /// only pushes a new difficulty once the observed rate drifts outside the
+    /// configured dead-zone, clamped to a max 4x step and the configured bounds.
+    async fn maybe_retarget_difficulty(&self, connection: &Arc<MinerConnection>) -> Result<()> {
+        const MAX_STEP: f64 = 4.0;
+
+        let Some(observed_rate) = connection.observed_shares_per_minute().await else {
+            return Ok(());
+        };
+
+        let config = self.coordinator.config();
+        let target_rate = config.vardiff_target_shares_per_minute;
+        if target_rate <= 0.0 || observed_rate <= 0.0 {
+            return Ok(());
+        }
+
+        let variance = config.vardiff_variance_percent / 100.0;
+        let ratio = observed_rate / target_rate;
+        if ratio <= 1.0 + variance && ratio >= 1.0 - variance {
+            return Ok(());
+        }
+
+        let clamped_ratio = ratio.clamp(1.0 / MAX_STEP, MAX_STEP);
+        let current = connection.current_difficulty();
+        // `minimum-difficulty` (negotiated via `mining.configure`) raises the
+        // floor the retargeter won't go below, on top of the pool's own.
+        let min_difficulty = config.vardiff_min_difficulty.max(connection.minimum_difficulty());
+        let new_difficulty = ((current as f64) * clamped_ratio)
+            .round()
+            .clamp(min_difficulty as f64, config.vardiff_max_difficulty as f64)
+            as u64;
+
+        if new_difficulty == current {
+            return Ok(());
+        }
+
+        connection.set_difficulty(new_difficulty);
+        debug!(
+            "Retargeted {} difficulty {} -> {} (observed {:.1} shares/min, target {:.1})",
+            connection.id, current, new_difficulty, observed_rate, target_rate
+        );
+
+        if let Some(worker_name) = connection.worker_name().await {
+            self.coordinator
+                .update_miner_difficulty(&worker_name, new_difficulty)
+                .await?;
+        }
+
+        self.push_difficulty(connection, new_difficulty).await
+    }
+
     async fn handle_stratum_request(
         &self,
         connection: Arc<MinerConnection>,
@@ -89,41 +319,36 @@ impl StratumServer {
                     id,
                     result: json!([
                         ["mining.notify", subscription_id],
-                        "00000000", // Extra nonce 1
-                        4           // Extra nonce 2 size
+                        connection.extranonce1(),
+                        EXTRANONCE2_SIZE,
                     ]),
                 })
             }
             
             StratumRequest::Authorize { id, worker_name, password: _ } => {
-                // Update connection with worker name
-                let mut connections = self.active_connections.write().await;
-                if let Some(conn) = connections.get_mut(&connection.id) {
-                    let mut_conn = Arc::get_mut(conn).unwrap();
-                    mut_conn.worker_name = Some(worker_name.clone());
-                    mut_conn.authorized = true;
-                }
-                
-                // Register miner with coordinator
-                self.coordinator.register_miner(&connection.address.to_string(), &worker_name).await?;
+                // Update connection with worker name. `connection` is always
+                // reached through a shared `Arc` (this handler, the
+                // `active_connections` map, and the job forwarder each hold a
+                // clone), so strong_count is never 1 here -- mutate through
+                // the fields' own interior mutability instead of Arc::get_mut.
+                connection.set_worker_name(worker_name.clone()).await;
+                connection.set_authorized(true);
+
+                // Register miner with coordinator, keyed by worker name --
+                // the same key `submit_share` looks shares up under (see
+                // `ShareSubmission::miner_id` below), so stats actually
+                // accumulate onto the record this call creates instead of a
+                // separate, never-updated one keyed by socket address.
+                self.coordinator.register_miner(&worker_name, &worker_name).await?;
                 
                 info!("Miner {} authorized as {}", connection.id, worker_name);
                 
-                // Send current job if available
+                // Send current job if available, so a freshly authorized miner
+                // doesn't have to wait for the next broadcast.
                 if let Some(job) = self.coordinator.get_current_job().await? {
-                    let notification = StratumResponse::Notification {
-                        method: "mining.notify".to_string(),
-                        params: json!({
-                            "job_id": job.id,
-                            "block_commitment": hex::encode(&job.block_commitment),
-                            "target": hex::encode(&job.target),
-                            "share_target": hex::encode(&job.share_target),
-                            "clean_jobs": true,
-                        }),
-                    };
-                    
-                    let message = serde_json::to_string(&notification.to_message()).unwrap();
-                    connection.send_message(message).await?;
+                    let notification = JobNotification { job, clean_jobs: true };
+                    let frame = self.render_job_for(&notification, &connection).await;
+                    connection.send_frame(frame).await?;
                 }
                 
                 Ok(StratumResponse::Result {
@@ -132,88 +357,293 @@ impl StratumServer {
                 })
             }
             
+            StratumRequest::Configure {
+                id,
+                version_rolling_mask,
+                version_rolling_min_bit_count,
+                minimum_difficulty,
+                subscribe_extranonce,
+            } => {
+                let granted = match version_rolling_mask {
+                    Some(requested) => {
+                        let granted = requested & SUPPORTED_VERSION_ROLLING_MASK;
+                        let min_bit_count = version_rolling_min_bit_count.unwrap_or(0);
+                        if granted.count_ones() < min_bit_count {
+                            debug!(
+                                "Miner {} requested version-rolling but granted mask {:#x} has fewer than {} bits",
+                                connection.id, granted, min_bit_count
+                            );
+                            None
+                        } else {
+                            Some(granted)
+                        }
+                    }
+                    None => None,
+                };
+
+                let mut result = match granted {
+                    Some(mask) => {
+                        connection.set_version_rolling_mask(mask);
+                        info!("Granted version-rolling mask {:#x} to miner {}", mask, connection.id);
+                        json!({
+                            "version-rolling": true,
+                            "version-rolling.mask": format!("{:08x}", mask),
+                        })
+                    }
+                    None => json!({ "version-rolling": false }),
+                };
+                let result_obj = result.as_object_mut().expect("constructed above as an object");
+
+                if let Some(minimum) = minimum_difficulty {
+                    connection.set_minimum_difficulty(minimum);
+                    info!("Miner {} requested minimum difficulty {}", connection.id, minimum);
+                    result_obj.insert("minimum-difficulty".to_string(), json!(true));
+                    result_obj.insert("minimum-difficulty.value".to_string(), json!(minimum));
+                }
+
+                if subscribe_extranonce {
+                    connection.set_subscribed_extranonce(true);
+                    result_obj.insert("subscribe-extranonce".to_string(), json!(true));
+                    self.push_extranonce(&connection, connection.extranonce1(), EXTRANONCE2_SIZE).await?;
+                }
+
+                Ok(StratumResponse::Result { id, result })
+            }
+
             StratumRequest::Submit { id, job_id, share_data, .. } => {
-                let worker_name = connection.worker_name.as_ref()
+                let worker_name = connection.worker_name().await
                     .ok_or_else(|| PoolError::MinerNotFound(connection.id.clone()))?;
-                
+
+                if !self.is_job_current(&job_id).await {
+                    let error = if self.is_job_stale(&job_id).await {
+                        self.coordinator.stats().record_stale(&worker_name).await;
+                        PoolError::StaleShare(job_id.clone())
+                    } else {
+                        PoolError::JobNotFound(job_id.clone())
+                    };
+                    Self::log_share_result(&worker_name, &job_id, false, &error.to_string(), 0);
+                    return Ok(StratumResponse::Error {
+                        id,
+                        error: Self::reject_error(&error),
+                    });
+                }
+
+                let version_bits = match &share_data {
+                    super::protocol::ShareSubmissionData::ComputationProof { version_bits, .. } => *version_bits,
+                    super::protocol::ShareSubmissionData::ValidBlock { version_bits, .. } => *version_bits,
+                };
+                let granted_mask = connection.version_rolling_mask();
+                if version_bits & !granted_mask != 0 {
+                    Self::log_share_result(&worker_name, &job_id, false, "version_rolling_violation", 0);
+                    return Ok(StratumResponse::Error {
+                        id,
+                        error: Self::reject_error(&PoolError::VersionRollingViolation),
+                    });
+                }
+
+                let (raw_nonce, extranonce2) = match &share_data {
+                    super::protocol::ShareSubmissionData::ComputationProof { nonce, extranonce2, .. } => (*nonce, extranonce2.clone()),
+                    super::protocol::ShareSubmissionData::ValidBlock { nonce, extranonce2, .. } => (*nonce, extranonce2.clone()),
+                };
+                if extranonce2.len() != EXTRANONCE2_SIZE as usize * 2 {
+                    Self::log_share_result(&worker_name, &job_id, false, "invalid_extranonce2_length", 0);
+                    return Ok(StratumResponse::Error {
+                        id,
+                        error: StratumError {
+                            code: -32602,
+                            message: "Invalid extranonce2 length".to_string(),
+                            data: None,
+                        },
+                    });
+                }
+                let nonce = Self::reconstruct_nonce(connection.extranonce1(), &extranonce2, raw_nonce)?;
+
                 // Create share submission
                 let share_type = match share_data {
-                    super::protocol::ShareSubmissionData::ComputationProof { witness_commitment, computation_steps } => {
+                    super::protocol::ShareSubmissionData::ComputationProof { witness_commitment, computation_steps, version_bits, extranonce2, .. } => {
                         crate::shares::ShareType::ComputationProof {
-                            nonce: 0, // Should be extracted from actual submission
+                            nonce,
                             witness_commitment,
                             computation_steps,
+                            version_bits,
+                            extranonce2,
                         }
                     }
-                    super::protocol::ShareSubmissionData::ValidBlock { proof } => {
+                    super::protocol::ShareSubmissionData::ValidBlock { proof, version_bits, extranonce2, .. } => {
                         crate::shares::ShareType::ValidBlock {
-                            nonce: 0, // Should be extracted from actual submission
+                            nonce,
                             proof,
+                            version_bits,
+                            extranonce2,
                         }
                     }
                 };
-                
+
+                let difficulty = connection.current_difficulty();
                 let submission = crate::shares::ShareSubmission {
-                    job_id,
+                    job_id: job_id.clone(),
                     miner_id: worker_name.clone(),
                     share_type,
                 };
-                
-                // Submit to coordinator
-                match self.coordinator.submit_share(submission).await {
+
+                // Submit to coordinator. The validator itself enforces that the
+                // share clears `connection.current_difficulty()` and weights
+                // reward_units/total_difficulty accounting by it, so a share
+                // that doesn't meet the assigned difficulty comes back as
+                // `Err(PoolError::InsufficientDifficulty)` below rather than
+                // needing a second check here.
+                match self.coordinator.submit_share(submission, difficulty).await {
                     Ok(validation) => {
                         if validation.is_block {
                             info!("BLOCK FOUND by {}!", worker_name);
                         }
-                        
+
+                        connection.record_accepted_share().await;
+                        self.maybe_retarget_difficulty(&connection).await?;
+                        Self::log_share_result(&worker_name, &job_id, true, "accepted", difficulty);
+
                         Ok(StratumResponse::Result {
                             id,
                             result: json!(true),
                         })
                     }
                     Err(e) => {
-                        warn!("Share rejected from {}: {}", worker_name, e);
+                        Self::log_share_result(&worker_name, &job_id, false, &e.to_string(), difficulty);
                         Ok(StratumResponse::Error {
                             id,
-                            error: StratumError {
-                                code: -32603,
-                                message: e.to_string(),
-                                data: None,
-                            },
+                            error: Self::reject_error(&e),
                         })
                     }
                 }
             }
             
             StratumRequest::GetStatus { id } => {
-                let stats = self.coordinator.get_pool_stats().await?;
+                let pool_stats = self.coordinator.get_pool_stats().await?;
+                let worker_stats = match connection.worker_name().await {
+                    Some(worker_name) => self.coordinator.stats().worker_snapshot(&worker_name).await,
+                    None => None,
+                };
                 Ok(StratumResponse::Result {
                     id,
-                    result: serde_json::to_value(stats)?,
+                    result: json!({
+                        "pool": pool_stats,
+                        "worker": worker_stats,
+                        "vardiff": {
+                            "difficulty": connection.current_difficulty(),
+                            "observed_shares_per_minute": connection.observed_shares_per_minute().await,
+                        },
+                    }),
                 })
             }
         }
     }
 }
 
+#[async_trait::async_trait]
+impl JobDispatcher for StratumServer {
+    async fn dispatch_job(&self, job: JobTemplate, clean_jobs: bool) {
+        {
+            let mut jobs = self.outstanding_jobs.write().await;
+            let mut retired = self.retired_jobs.write().await;
+
+            if clean_jobs {
+                retired.extend(jobs.drain(..));
+            }
+            jobs.push_back(job.id.clone());
+            while jobs.len() > MAX_OUTSTANDING_JOBS {
+                if let Some(evicted) = jobs.pop_front() {
+                    retired.push_back(evicted);
+                }
+            }
+            while retired.len() > RETIRED_JOBS_LEN {
+                retired.pop_front();
+            }
+        }
+
+        let _ = self.job_broadcaster.send(JobNotification { job, clean_jobs });
+    }
+
+    async fn push_difficulty(&self, connection: &Arc<MinerConnection>, difficulty: u64) -> Result<()> {
+        let notification = StratumResponse::Notification {
+            method: "mining.set_difficulty".to_string(),
+            params: json!([difficulty]),
+        };
+        connection.send_frame(notification.to_message()).await
+    }
+}
+
+impl StratumServer {
+    /// Pushes `mining.set_extranonce` to a connection that negotiated
+    /// `subscribe-extranonce`, re-announcing the `extranonce1` prefix it was
+    /// already assigned at connect time (see `MinerConnection::extranonce1`).
+    async fn push_extranonce(&self, connection: &Arc<MinerConnection>, extranonce1: &str, extranonce2_size: u32) -> Result<()> {
+        let notification = StratumResponse::Notification {
+            method: "mining.set_extranonce".to_string(),
+            params: json!([extranonce1, extranonce2_size]),
+        };
+        connection.send_frame(notification.to_message()).await
+    }
+
+    /// Folds a connection's assigned `extranonce1` and a share's submitted
+    /// `extranonce2` into a 64-bit prefix (`extranonce1` in the high 32 bits,
+    /// `extranonce2` in the low 32), then XORs in the miner's local nonce to land on the exact value
+    /// to verify -- mirroring classic Stratum's `extranonce1 || extranonce2
+    /// || nonce` header layout within a single `u64` nonce field.
+    fn reconstruct_nonce(extranonce1: &str, extranonce2: &str, local_nonce: u64) -> Result<u64> {
+        let e1 = u32::from_str_radix(extranonce1, 16)
+            .map_err(|_| PoolError::ShareValidation("invalid extranonce1".to_string()))?;
+        let e2 = u32::from_str_radix(extranonce2, 16)
+            .map_err(|_| PoolError::ShareValidation("invalid extranonce2".to_string()))?;
+        let prefix = ((e1 as u64) << 32) | (e2 as u64);
+        Ok(prefix ^ local_nonce)
+    }
+
+    /// Maps a `PoolError` surfaced while handling `mining.submit` to the
+    /// conventional pool share-reject code/message (the 20-24 range common
+    /// to ckpool-style Stratum servers), so standard miner software can
+    /// react to the right one -- e.g. re-fetch work on a stale-job reject --
+    /// instead of treating every rejection as the same generic server error.
+    fn reject_error(err: &PoolError) -> StratumError {
+        let (code, message) = match err {
+            PoolError::StaleShare(_) => (21, "Job not found (stale)".to_string()),
+            PoolError::JobNotFound(_) => (21, "Job not found".to_string()),
+            PoolError::DuplicateShare => (22, "Duplicate share".to_string()),
+            PoolError::InsufficientDifficulty => (23, "Low difficulty share".to_string()),
+            PoolError::InvalidProof => (20, "Invalid proof".to_string()),
+            PoolError::VersionRollingViolation => (20, err.to_string()),
+            PoolError::MinerNotFound(_) => (24, "Unauthorized worker".to_string()),
+            _ => (20, err.to_string()),
+        };
+        StratumError { code, message, data: None }
+    }
+
+    /// Emits one structured log line per `mining.submit`, so operators can
+    /// analyze reject rates and distinguish reasons without cross-referencing
+    /// the separate `warn!` lines each rejection branch used to log.
+    fn log_share_result(worker_name: &str, job_id: &str, accepted: bool, reason: &str, difficulty: u64) {
+        info!(
+            "share result: worker={} job_id={} accepted={} reason={} difficulty={}",
+            worker_name, job_id, accepted, reason, difficulty
+        );
+    }
+}
+
 #[async_trait::async_trait]
 impl ConnectionHandler for StratumServer {
     async fn on_connect(&self, connection: Arc<MinerConnection>) {
         info!("New miner connection from {}", connection.address);
+        self.spawn_job_forwarder(connection.clone());
         self.active_connections.write().await.insert(connection.id.clone(), connection);
     }
     
-    async fn on_message(&self, connection: Arc<MinerConnection>, message: String) -> Result<()> {
-        debug!("Received message from {}: {}", connection.id, message);
-        
-        let stratum_msg: StratumMessage = serde_json::from_str(&message)?;
-        let request = stratum_msg.parse_request()
+    async fn on_message(&self, connection: Arc<MinerConnection>, frame: Frame) -> Result<()> {
+        debug!("Received message from {}: {:?}", connection.id, frame);
+
+        let request = frame.parse_request()
             .map_err(|e| PoolError::StratumProtocol(format!("Invalid request: {:?}", e)))?;
-        
+
         let response = self.handle_stratum_request(connection.clone(), request).await?;
-        let response_msg = serde_json::to_string(&response.to_message())?;
-        
-        connection.send_message(response_msg).await?;
+        connection.send_frame(response.to_message()).await?;
         Ok(())
     }
     
@@ -221,10 +651,11 @@ impl ConnectionHandler for StratumServer {
         info!("Miner {} disconnected", connection.id);
         self.active_connections.write().await.remove(&connection.id);
         
-        if let Some(worker_name) = &connection.worker_name {
-            if let Err(e) = self.coordinator.unregister_miner(worker_name).await {
+        if let Some(worker_name) = connection.worker_name().await {
+            if let Err(e) = self.coordinator.unregister_miner(&worker_name).await {
                 error!("Failed to unregister miner {}: {}", worker_name, e);
             }
+            self.coordinator.stats().mark_disconnected(&worker_name).await;
         }
     }
 }
@@ -251,4 +682,19 @@ async fn miner_stats_handler(
 ) -> Result<Json<serde_json::Value>, PoolError> {
     let stats = server.coordinator.get_miner_stats(&address).await?;
     Ok(Json(stats))
+}
+
+async fn workers_handler(
+    State(server): State<Arc<StratumServer>>,
+) -> Json<Vec<crate::stats::WorkerStatsSnapshot>> {
+    Json(server.coordinator.stats().all_snapshots().await)
+}
+
+async fn worker_handler(
+    State(server): State<Arc<StratumServer>>,
+    axum::extract::Path(address): axum::extract::Path<String>,
+) -> Result<Json<crate::stats::WorkerStatsSnapshot>, PoolError> {
+    server.coordinator.stats().worker_snapshot(&address).await
+        .map(Json)
+        .ok_or_else(|| PoolError::MinerNotFound(address))
 }
\ No newline at end of file
@@ -0,0 +1,233 @@
+//! Transport abstraction for the Stratum connection: the existing JSON
+//! over WebSocket framing (`WsJsonTransport`), and a Noise_NX-shaped
+//! encrypted binary framing (`NoiseTransport`) for the SV2-style listener.
+//! Both carry the same logical [`StratumMessage`], so `ConnectionHandler`
+//! impls don't need to know which wire format a given miner connected over.
+//!
+//! `NoiseTransport` is the responder half of the handshake implemented as
+//! the initiator in the miner-side `pool_sv2::NoiseSession` -- same X25519
+//! ECDH derivation, key labels, and sealed length-prefixed framing. The
+//! sealed payloads differ: this side carries JSON-encoded `StratumMessage`s,
+//! while `NoiseSession` carries type-tagged binary SV2 messages, so the two
+//! complete a handshake but don't exchange mining messages with each other.
+
+use async_trait::async_trait;
+use axum::extract::ws::{Message, WebSocket};
+use futures::{SinkExt, StreamExt};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+use crate::error::{PoolError, Result};
+use super::protocol::StratumMessage;
+
+/// A framed unit exchanged over a `Transport` -- just the logical Stratum
+/// message, independent of whether the wire carries it as WebSocket text
+/// or an encrypted length-prefixed binary frame.
+pub type Frame = StratumMessage;
+
+/// One connection's wire format. Implemented by both the current
+/// plaintext WebSocket path and the SV2-style encrypted binary path, so
+/// `handle_websocket`/the SV2 listener can speak to miners through one
+/// interface regardless of which transport they negotiated.
+#[async_trait]
+pub trait Transport: Send {
+    async fn send(&mut self, frame: Frame) -> Result<()>;
+
+    /// Returns `Ok(None)` once the peer closes the connection.
+    async fn recv(&mut self) -> Result<Option<Frame>>;
+
+    /// Closes the connection from our side.
+    async fn close(&mut self) -> Result<()>;
+}
+
+/// The existing transport: one `StratumMessage` per WebSocket text frame,
+/// JSON-encoded exactly as miners already speak today.
+pub struct WsJsonTransport {
+    ws: WebSocket,
+}
+
+impl WsJsonTransport {
+    pub fn new(ws: WebSocket) -> Self {
+        Self { ws }
+    }
+}
+
+#[async_trait]
+impl Transport for WsJsonTransport {
+    async fn send(&mut self, frame: Frame) -> Result<()> {
+        let text = serde_json::to_string(&frame)?;
+        self.ws
+            .send(Message::Text(text))
+            .await
+            .map_err(|e| PoolError::WebSocket(e.to_string()))
+    }
+
+    async fn recv(&mut self) -> Result<Option<Frame>> {
+        loop {
+            match self.ws.next().await {
+                Some(Ok(Message::Text(text))) => return Ok(Some(serde_json::from_str(&text)?)),
+                Some(Ok(Message::Close(_))) | None => return Ok(None),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(PoolError::WebSocket(e.to_string())),
+            }
+        }
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.ws
+            .send(Message::Close(None))
+            .await
+            .map_err(|e| PoolError::WebSocket(e.to_string()))
+    }
+}
+
+const AUTH_TAG_LEN: usize = 32;
+
+/// Stratum V2-style encrypted binary transport over a raw stream (the SV2
+/// listener hands it a freshly accepted `TcpStream`). The handshake and
+/// AEAD framing mirror `pool_sv2::NoiseSession` on the miner side -- a
+/// simplified stand-in for real Noise_NX (no handshake-pattern/static-key
+/// authentication, so it doesn't resist an active man-in-the-middle), but
+/// the transport key is a genuine X25519 ECDH output: a passive observer of
+/// the wire sees only the two public keys, not the shared secret itself.
+pub struct NoiseTransport<S> {
+    stream: S,
+    send_key: [u8; 32],
+    recv_key: [u8; 32],
+    send_nonce: u64,
+    recv_nonce: u64,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> NoiseTransport<S> {
+    /// Runs the handshake as responder: read the initiator's X25519 public
+    /// key, reply with our own, then derive transport keys from the ECDH
+    /// shared secret (order-independent, so either side can go first).
+    pub async fn handshake_responder(mut stream: S) -> Result<Self> {
+        let mut remote_public_bytes = [0u8; 32];
+        stream
+            .read_exact(&mut remote_public_bytes)
+            .await
+            .map_err(|e| PoolError::WebSocket(e.to_string()))?;
+
+        let local_secret = EphemeralSecret::random_from_rng(rand::rngs::OsRng);
+        let local_public = PublicKey::from(&local_secret);
+        stream
+            .write_all(local_public.as_bytes())
+            .await
+            .map_err(|e| PoolError::WebSocket(e.to_string()))?;
+        stream.flush().await.map_err(|e| PoolError::WebSocket(e.to_string()))?;
+
+        let remote_public = PublicKey::from(remote_public_bytes);
+        let shared_secret = local_secret.diffie_hellman(&remote_public);
+
+        Ok(Self {
+            stream,
+            // Mirrors the initiator's labels with the direction flipped, so
+            // the two halves land on the same keys for the same direction.
+            send_key: Self::derive_key(shared_secret.as_bytes(), b"responder->initiator"),
+            recv_key: Self::derive_key(shared_secret.as_bytes(), b"initiator->responder"),
+            send_nonce: 0,
+            recv_nonce: 0,
+        })
+    }
+
+    fn derive_key(secret: &[u8; 32], label: &[u8]) -> [u8; 32] {
+        let mut buf = Vec::with_capacity(secret.len() + label.len());
+        buf.extend_from_slice(secret);
+        buf.extend_from_slice(label);
+        Sha256::digest(&buf).into()
+    }
+
+    /// Keystream-XOR encryption with an appended authentication tag over
+    /// (key, nonce, plaintext) -- an encrypt-and-authenticate construction
+    /// standing in for a real AEAD cipher.
+    fn seal(key: &[u8; 32], nonce: u64, plaintext: &[u8]) -> Vec<u8> {
+        let keystream = Self::keystream(key, nonce, plaintext.len());
+        let mut sealed: Vec<u8> = plaintext.iter().zip(keystream.iter()).map(|(p, k)| p ^ k).collect();
+        sealed.extend_from_slice(&Self::tag(key, nonce, plaintext));
+        sealed
+    }
+
+    fn open(key: &[u8; 32], nonce: u64, sealed: &[u8]) -> Result<Vec<u8>> {
+        if sealed.len() < AUTH_TAG_LEN {
+            return Err(PoolError::WebSocket("SV2 frame too short for auth tag".to_string()));
+        }
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - AUTH_TAG_LEN);
+        let keystream = Self::keystream(key, nonce, ciphertext.len());
+        let plaintext: Vec<u8> = ciphertext.iter().zip(keystream.iter()).map(|(c, k)| c ^ k).collect();
+
+        if Self::tag(key, nonce, &plaintext) != tag {
+            return Err(PoolError::WebSocket("SV2 frame authentication failed".to_string()));
+        }
+        Ok(plaintext)
+    }
+
+    fn tag(key: &[u8; 32], nonce: u64, plaintext: &[u8]) -> [u8; 32] {
+        let mut buf = Vec::with_capacity(key.len() + 8 + plaintext.len());
+        buf.extend_from_slice(key);
+        buf.extend_from_slice(&nonce.to_le_bytes());
+        buf.extend_from_slice(plaintext);
+        Sha256::digest(&buf).into()
+    }
+
+    fn keystream(key: &[u8; 32], nonce: u64, len: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(len);
+        let mut counter: u64 = 0;
+        while out.len() < len {
+            let mut block_input = Vec::with_capacity(key.len() + 16);
+            block_input.extend_from_slice(key);
+            block_input.extend_from_slice(&nonce.to_le_bytes());
+            block_input.extend_from_slice(&counter.to_le_bytes());
+            out.extend_from_slice(&Sha256::digest(&block_input));
+            counter += 1;
+        }
+        out.truncate(len);
+        out
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> Transport for NoiseTransport<S> {
+    async fn send(&mut self, frame: Frame) -> Result<()> {
+        let plaintext = serde_json::to_vec(&frame)?;
+        let sealed = Self::seal(&self.send_key, self.send_nonce, &plaintext);
+        self.send_nonce += 1;
+
+        self.stream
+            .write_u32(sealed.len() as u32)
+            .await
+            .map_err(|e| PoolError::WebSocket(e.to_string()))?;
+        self.stream
+            .write_all(&sealed)
+            .await
+            .map_err(|e| PoolError::WebSocket(e.to_string()))?;
+        self.stream.flush().await.map_err(|e| PoolError::WebSocket(e.to_string()))
+    }
+
+    async fn recv(&mut self) -> Result<Option<Frame>> {
+        let len = match self.stream.read_u32().await {
+            Ok(len) => len as usize,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(PoolError::WebSocket(e.to_string())),
+        };
+        let mut sealed = vec![0u8; len];
+        self.stream
+            .read_exact(&mut sealed)
+            .await
+            .map_err(|e| PoolError::WebSocket(e.to_string()))?;
+
+        let plaintext = Self::open(&self.recv_key, self.recv_nonce, &sealed)?;
+        self.recv_nonce += 1;
+        Ok(Some(serde_json::from_slice(&plaintext)?))
+    }
+
+    /// No SV2 close/disconnect message type is implemented in this backend's
+    /// subset, so we just shut down the underlying stream (mirrors the same
+    /// simplification on the miner-side `pool_sv2::Sv2PoolClient`).
+    async fn close(&mut self) -> Result<()> {
+        AsyncWriteExt::shutdown(&mut self.stream)
+            .await
+            .map_err(|e| PoolError::WebSocket(e.to_string()))
+    }
+}
@@ -1,4 +1,5 @@
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -14,12 +15,15 @@ use nockvm::noun::{Atom, D, T};
 use nockvm_macros::tas;
 use tempfile::tempdir;
 use tracing::{instrument, warn, info, error, debug};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use anyhow::anyhow;
 use bytes::Bytes;
-use crate::pool_client::{PoolClient, PoolJob, ShareSubmission, ShareType};
+use crate::pool_client::{
+    PoolClient, PoolConnectionStatus, PoolJob, PoolProtocol, PoolTransport, ShareSubmission, ShareType,
+};
+use crate::pool_sv2::Sv2PoolClient;
 
 
 pub enum MiningWire {
@@ -63,10 +67,65 @@ pub struct MiningKeyConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PoolMiningConfig {
-    pub pool_url: String,
+    /// Ordered list of pool endpoints: the primary followed by backups.
+    /// `PoolClient`/`Sv2PoolClient` start at index 0 and rotate to the next
+    /// endpoint after repeated consecutive connection failures.
+    pub pool_urls: Vec<String>,
     pub worker_name: String,
     pub worker_password: Option<String>,
+    /// Starting point for `pool_client::PoolClient`'s own vardiff retargeter,
+    /// which adjusts it at runtime to keep share submissions near one every
+    /// ~10s. The SV2 backend (`Sv2PoolClient`) doesn't read it.
     pub share_difficulty_multiplier: f64,
+    /// Which wire protocol to speak to the pool. Defaults to the legacy JSON backend.
+    #[serde(default)]
+    pub protocol: PoolProtocol,
+    /// Initial delay before the first reconnect attempt. Doubled (via
+    /// `reconnect_backoff_multiplier`) after each attempt that doesn't stay
+    /// up past `reconnect_stability_threshold_secs`, up to `reconnect_max_backoff_secs`.
+    #[serde(default = "default_reconnect_base_backoff_secs")]
+    pub reconnect_base_backoff_secs: u64,
+    /// Ceiling on the backoff delay between reconnect attempts.
+    #[serde(default = "default_reconnect_max_backoff_secs")]
+    pub reconnect_max_backoff_secs: u64,
+    /// Factor the backoff delay is scaled by after each attempt that doesn't
+    /// stay up past the stability threshold.
+    #[serde(default = "default_reconnect_backoff_multiplier")]
+    pub reconnect_backoff_multiplier: f64,
+    /// Relative jitter applied to each backoff delay, e.g. `0.5` for ±50%.
+    #[serde(default = "default_reconnect_jitter_percent")]
+    pub reconnect_jitter_percent: f64,
+    /// How long a connection must stay up before a subsequent failure is
+    /// treated as a fresh outage (resetting backoff and the attempt count)
+    /// rather than a continuation of the one that just ended.
+    #[serde(default = "default_reconnect_stability_threshold_secs")]
+    pub reconnect_stability_threshold_secs: u64,
+    /// Give up after this many consecutive reconnect attempts that never
+    /// reach the stability threshold, surfacing a terminal
+    /// `PoolConnectionStatus::Exhausted` instead of retrying forever.
+    /// `None` (the default) retries indefinitely.
+    #[serde(default)]
+    pub max_reconnect_attempts: Option<u32>,
+}
+
+fn default_reconnect_base_backoff_secs() -> u64 {
+    1
+}
+
+fn default_reconnect_max_backoff_secs() -> u64 {
+    60
+}
+
+fn default_reconnect_backoff_multiplier() -> f64 {
+    2.0
+}
+
+fn default_reconnect_jitter_percent() -> f64 {
+    0.5
+}
+
+fn default_reconnect_stability_threshold_secs() -> u64 {
+    30
 }
 
 #[derive(Debug, Clone)]
@@ -98,25 +157,42 @@ impl FromStr for MiningKeyConfig {
     }
 }
 
+/// Number of disjoint nonce sub-ranges to search in parallel for solo mining.
+/// `None` defaults to the number of available cores, mirroring how most of
+/// our other worker-count knobs (e.g. proof generation) size themselves.
+fn resolve_mining_threads(mining_threads: Option<usize>) -> usize {
+    mining_threads.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    })
+}
+
+/// `shutdown` is watched by both driver loops alongside `init_complete_tx`:
+/// once it flips to `true`, the loop stops accepting new candidates/jobs,
+/// drains whatever mining/share-submission work is already in flight, and
+/// (for pool mode) closes the pool session before returning.
 pub fn create_mining_driver(
     mining_mode: Option<MiningMode>,
     mine: bool,
     init_complete_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    mining_threads: Option<usize>,
+    shutdown: watch::Receiver<bool>,
 ) -> IODriverFn {
     match mining_mode {
         Some(MiningMode::Pool(pool_config)) => {
             if mine {
-                create_pool_mining_driver(pool_config, init_complete_tx)
+                create_pool_mining_driver(pool_config, init_complete_tx, shutdown)
             } else {
                 // Pool mode requires mining to be enabled
                 warn!("Pool mode specified but mining is disabled. Using solo mode.");
-                create_solo_mining_driver(None, false, init_complete_tx)
+                create_solo_mining_driver(None, false, init_complete_tx, mining_threads, shutdown)
             }
         }
         Some(MiningMode::Solo(mining_config)) => {
-            create_solo_mining_driver(Some(mining_config), mine, init_complete_tx)
+            create_solo_mining_driver(Some(mining_config), mine, init_complete_tx, mining_threads, shutdown)
         }
-        None => create_solo_mining_driver(None, mine, init_complete_tx),
+        None => create_solo_mining_driver(None, mine, init_complete_tx, mining_threads, shutdown),
     }
 }
 
@@ -124,7 +200,10 @@ fn create_solo_mining_driver(
     mining_config: Option<Vec<MiningKeyConfig>>,
     mine: bool,
     init_complete_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    mining_threads: Option<usize>,
+    mut shutdown: watch::Receiver<bool>,
 ) -> IODriverFn {
+    let mining_threads = resolve_mining_threads(mining_threads);
     Box::new(move |mut handle| {
         Box::pin(async move {
             let Some(configs) = mining_config else {
@@ -160,11 +239,24 @@ fn create_solo_mining_driver(
             if !mine {
                 return Ok(());
             }
-            let mut next_attempt: Option<NounSlab> = None;
-            let mut current_attempt: tokio::task::JoinSet<()> = tokio::task::JoinSet::new();
+            // One task per nonce sub-range for the current candidate. All K
+            // tasks of a generation share a single cancel flag/channel: the
+            // first to produce a mined effect cancels the rest, and a fresher
+            // candidate cancels the whole generation so mining always chases
+            // the latest chain tip instead of finishing out a stale one.
+            let mut current_attempt: tokio::task::JoinSet<bool> = tokio::task::JoinSet::new();
+            let mut current_cancel = Arc::new(AtomicBool::new(false));
+            let mut cancel_tx: Option<watch::Sender<bool>> = None;
 
             loop {
                 tokio::select! {
+                    _ = shutdown.changed() => {
+                        if *shutdown.borrow() {
+                            info!("Solo mining driver shutting down: draining in-flight mining attempts");
+                            while current_attempt.join_next().await.is_some() {}
+                            return Ok(());
+                        }
+                    }
                     effect_res = handle.next_effect() => {
                         let Ok(effect) = effect_res else {
                           warn!("Error receiving effect in mining driver: {effect_res:?}");
@@ -182,26 +274,45 @@ fn create_solo_mining_driver(
                                 slab
                             };
                             if !current_attempt.is_empty() {
-                                next_attempt = Some(candidate_slab);
-                            } else {
-                                let (cur_handle, attempt_handle) = handle.dup();
-                                handle = cur_handle;
-                                current_attempt.spawn(mining_attempt(candidate_slab, attempt_handle));
+                                debug!("Fresher candidate arrived, aborting stale mining attempts");
+                                current_cancel.store(true, Ordering::Relaxed);
+                                if let Some(tx) = &cancel_tx {
+                                    let _ = tx.send(true);
+                                }
+                                current_attempt.abort_all();
+                                // Drain the aborted tasks so their temp snapshot dirs are
+                                // torn down (via Drop) before the new generation starts.
+                                while current_attempt.join_next().await.is_some() {}
                             }
+
+                            let (cancel, tx) = spawn_mining_generation(&mut handle, &mut current_attempt, &candidate_slab, mining_threads);
+                            current_cancel = cancel;
+                            cancel_tx = Some(tx);
                         }
                     },
                     mining_attempt_res = current_attempt.join_next(), if !current_attempt.is_empty()  => {
-                        if let Some(Err(e)) = mining_attempt_res {
-                            warn!("Error during mining attempt: {e:?}");
+                        match mining_attempt_res {
+                            Some(Ok(true)) => {
+                                debug!("A nonce-range attempt found a block, cancelling its siblings");
+                                current_cancel.store(true, Ordering::Relaxed);
+                                if let Some(tx) = &cancel_tx {
+                                    let _ = tx.send(true);
+                                }
+                                current_attempt.abort_all();
+                                while current_attempt.join_next().await.is_some() {}
+                            }
+                            Some(Ok(false)) => {
+                                // This sub-range exhausted its slice without finding a
+                                // solution; other sub-ranges of the same generation may
+                                // still be searching.
+                            }
+                            Some(Err(e)) => {
+                                if !e.is_cancelled() {
+                                    warn!("Error during mining attempt: {e:?}");
+                                }
+                            }
+                            None => {}
                         }
-                        let Some(candidate_slab) = next_attempt else {
-                            continue;
-                        };
-                        next_attempt = None;
-                        let (cur_handle, attempt_handle) = handle.dup();
-                        handle = cur_handle;
-                        current_attempt.spawn(mining_attempt(candidate_slab, attempt_handle));
-
                     }
                 }
             }
@@ -209,23 +320,114 @@ fn create_solo_mining_driver(
     })
 }
 
-pub async fn mining_attempt(candidate: NounSlab, handle: NockAppHandle) -> () {
+/// Splits the full 64-bit nonce space into `threads` disjoint, roughly-equal
+/// `(nonce_start, nonce_range)` sub-ranges for parallel solo mining.
+fn partition_nonce_range(threads: usize) -> Vec<(u64, u64)> {
+    let threads = (threads.max(1)) as u64;
+    let chunk = u64::MAX / threads;
+    (0..threads)
+        .map(|i| {
+            let nonce_start = i * chunk;
+            let nonce_range = if i == threads - 1 {
+                u64::MAX - nonce_start
+            } else {
+                chunk
+            };
+            (nonce_start, nonce_range)
+        })
+        .collect()
+}
+
+/// Wraps a mining candidate with an explicit nonce sub-range, the same way
+/// `spawn_pool_mining_task` threads `nonce_start`/`nonce_range` into the pool
+/// candidate config, so each parallel `mining_attempt` only searches its own
+/// slice of the nonce space.
+fn partition_candidate(candidate: &NounSlab, nonce_start: u64, nonce_range: u64) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let candidate_noun = unsafe { *candidate.root() };
+    let ranged_candidate = T(&mut slab, &[candidate_noun, D(nonce_start), D(nonce_range)]);
+    slab.set_root(ranged_candidate);
+    slab
+}
+
+/// Spawns one `mining_attempt` per nonce sub-range for `candidate`, all
+/// sharing a single cancel flag/channel, and returns that shared cancel state
+/// so the caller can cancel the whole generation later (a fresher candidate
+/// arriving, or one sub-range already finding a block).
+fn spawn_mining_generation(
+    handle: &mut NockAppHandle,
+    current_attempt: &mut tokio::task::JoinSet<bool>,
+    candidate_slab: &NounSlab,
+    threads: usize,
+) -> (Arc<AtomicBool>, watch::Sender<bool>) {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = watch::channel(false);
+    for (nonce_start, nonce_range) in partition_nonce_range(threads) {
+        let ranged_candidate = partition_candidate(candidate_slab, nonce_start, nonce_range);
+        let (cur_handle, attempt_handle) = handle.dup();
+        *handle = cur_handle;
+        current_attempt.spawn(mining_attempt(
+            ranged_candidate,
+            attempt_handle,
+            cancel.clone(),
+            rx.clone(),
+        ));
+    }
+    (cancel, tx)
+}
+
+/// Mines a single nonce sub-range of a candidate. Returns `true` if this
+/// sub-range produced a mined block, `false` if it was cancelled or
+/// exhausted its range without finding one.
+pub async fn mining_attempt(
+    candidate: NounSlab,
+    handle: NockAppHandle,
+    cancel: Arc<AtomicBool>,
+    mut cancel_rx: watch::Receiver<bool>,
+) -> bool {
     let snapshot_dir =
         tokio::task::spawn_blocking(|| tempdir().expect("Failed to create temporary directory"))
             .await
             .expect("Failed to create temporary directory");
+
+    if cancel.load(Ordering::Relaxed) {
+        debug!("Mining attempt cancelled before kernel load, discarding stale candidate");
+        return false;
+    }
+
     let hot_state = zkvm_jetpack::hot::produce_prover_hot_state();
     let snapshot_path_buf = snapshot_dir.path().to_path_buf();
     let jam_paths = JamPaths::new(snapshot_dir.path());
     // Spawns a new std::thread for this mining attempt
-    let kernel =
-        Kernel::load_with_hot_state_huge(snapshot_path_buf, jam_paths, KERNEL, &hot_state, false)
-            .await
-            .expect("Could not load mining kernel");
-    let effects_slab = kernel
-        .poke(MiningWire::Candidate.to_wire(), candidate)
-        .await
-        .expect("Could not poke mining kernel with candidate");
+    let kernel = tokio::select! {
+        result = Kernel::load_with_hot_state_huge(snapshot_path_buf, jam_paths, KERNEL, &hot_state, false) => {
+            result.expect("Could not load mining kernel")
+        }
+        _ = cancel_rx.changed() => {
+            debug!("Mining attempt cancelled during kernel load, discarding stale candidate");
+            return false;
+        }
+    };
+
+    // Checked cooperatively at the poke boundary: the prover itself runs on a
+    // blocking thread we can't preempt, so this is the last point we can bail
+    // out before sinking time into now-obsolete work.
+    if cancel.load(Ordering::Relaxed) {
+        debug!("Mining attempt cancelled at poke boundary, discarding stale candidate");
+        return false;
+    }
+
+    let effects_slab = tokio::select! {
+        result = kernel.poke(MiningWire::Candidate.to_wire(), candidate) => {
+            result.expect("Could not poke mining kernel with candidate")
+        }
+        _ = cancel_rx.changed() => {
+            debug!("Mining attempt cancelled during poke, discarding stale candidate");
+            return false;
+        }
+    };
+
+    let mut found = false;
     for effect in effects_slab.to_vec() {
         let Ok(effect_cell) = (unsafe { effect.root().as_cell() }) else {
             drop(effect);
@@ -236,8 +438,10 @@ pub async fn mining_attempt(candidate: NounSlab, handle: NockAppHandle) -> () {
                 .poke(MiningWire::Mined.to_wire(), effect)
                 .await
                 .expect("Could not poke nockchain with mined PoW");
+            found = true;
         }
     }
+    found
 }
 
 #[instrument(skip(handle, pubkey))]
@@ -320,21 +524,34 @@ async fn enable_mining(handle: &NockAppHandle, enable: bool) -> Result<PokeResul
 fn create_pool_mining_driver(
     pool_config: PoolMiningConfig,
     init_complete_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    mut shutdown: watch::Receiver<bool>,
 ) -> IODriverFn {
     Box::new(move |mut handle| {
         Box::pin(async move {
-            info!("Starting pool mining driver for {}", pool_config.pool_url);
-            
-            // Create pool client
-            let pool_client = match PoolClient::new(&pool_config).await {
-                Ok(client) => Arc::new(client),
-                Err(e) => {
-                    error!("Failed to create pool client: {}", e);
-                    if let Some(tx) = init_complete_tx {
-                        let _ = tx.send(());
+            info!("Starting pool mining driver for {:?} ({:?})", pool_config.pool_urls, pool_config.protocol);
+
+            // Create pool client for the configured wire protocol
+            let pool_client: Arc<dyn PoolTransport> = match pool_config.protocol {
+                PoolProtocol::Json => match PoolClient::new(&pool_config).await {
+                    Ok(client) => Arc::new(client),
+                    Err(e) => {
+                        error!("Failed to create pool client: {}", e);
+                        if let Some(tx) = init_complete_tx {
+                            let _ = tx.send(());
+                        }
+                        return Err(NockAppError::OtherError);
                     }
-                    return Err(NockAppError::OtherError);
-                }
+                },
+                PoolProtocol::Sv2 => match Sv2PoolClient::new(&pool_config).await {
+                    Ok(client) => Arc::new(client),
+                    Err(e) => {
+                        error!("Failed to create SV2 pool client: {}", e);
+                        if let Some(tx) = init_complete_tx {
+                            let _ = tx.send(());
+                        }
+                        return Err(NockAppError::OtherError);
+                    }
+                },
             };
             
             // Wait for authorization
@@ -353,14 +570,50 @@ fn create_pool_mining_driver(
             }
             
             info!("Successfully authorized with pool");
-            
+
             if let Some(tx) = init_complete_tx {
                 let _ = tx.send(());
             }
-            
+
+            // Tasks spawned for outstanding jobs (kernel load/poke) and their
+            // share submissions, tracked so shutdown can drain them instead
+            // of just dropping the process mid-submission.
+            let pending_effects: Arc<tokio::sync::Mutex<tokio::task::JoinSet<()>>> =
+                Arc::new(tokio::sync::Mutex::new(tokio::task::JoinSet::new()));
+
+            let mut connection_status = pool_client.connection_status();
+
             // Main pool mining loop
             loop {
                 tokio::select! {
+                    _ = shutdown.changed() => {
+                        if *shutdown.borrow() {
+                            info!("Pool mining driver shutting down: draining outstanding share submissions");
+                            {
+                                let mut pending = pending_effects.lock().await;
+                                while pending.join_next().await.is_some() {}
+                            }
+                            if let Err(e) = pool_client.close().await {
+                                warn!("Error closing pool connection during shutdown: {}", e);
+                            }
+                            return Ok(());
+                        }
+                    }
+
+                    // Pool exhausted its reconnect attempts (`max_reconnect_attempts`):
+                    // stop spinning and return an error rather than waiting on a dead
+                    // pool forever. This driver does not switch to solo mining.
+                    status_res = connection_status.changed() => {
+                        if status_res.is_ok() && *connection_status.borrow() == PoolConnectionStatus::Exhausted {
+                            error!("Pool reconnection exhausted, giving up on pool mining");
+                            {
+                                let mut pending = pending_effects.lock().await;
+                                while pending.join_next().await.is_some() {}
+                            }
+                            return Err(NockAppError::OtherError);
+                        }
+                    }
+
                     // Receive work from pool
                     job_res = pool_client.recv_job() => {
                         match job_res {
@@ -369,7 +622,7 @@ fn create_pool_mining_driver(
                                 let pool_client_clone = pool_client.clone();
                                 let (handle_copy, handle_new) = handle.dup();
                                 handle = handle_new;
-                                spawn_pool_mining_task(job, handle_copy, pool_client_clone).await;
+                                spawn_pool_mining_task(job, handle_copy, pool_client_clone, pending_effects.clone()).await;
                             }
                             Err(e) => {
                                 error!("Failed to receive job: {}", e);
@@ -378,7 +631,7 @@ fn create_pool_mining_driver(
                             }
                         }
                     }
-                    
+
                     // Note: Effects from pool mining are handled directly in spawn_pool_mining_task
                     // This is here for any other effects that might come through
                     effect_res = handle.next_effect() => {
@@ -397,9 +650,12 @@ fn create_pool_mining_driver(
 async fn spawn_pool_mining_task(
     job: PoolJob,
     handle: NockAppHandle,
-    pool_client: Arc<PoolClient>,
+    pool_client: Arc<dyn PoolTransport>,
+    pending_effects: Arc<tokio::sync::Mutex<tokio::task::JoinSet<()>>>,
 ) {
-    tokio::spawn(async move {
+    let pending_effects_outer = pending_effects.clone();
+    pending_effects.lock().await.spawn(async move {
+        let pending_effects = pending_effects_outer;
         let snapshot_dir = match tempdir() {
             Ok(dir) => dir,
             Err(e) => {
@@ -476,7 +732,7 @@ async fn spawn_pool_mining_task(
             
             // Store pool_client reference for the handler
             let pool_client_clone = pool_client.clone();
-            tokio::spawn(async move {
+            pending_effects.lock().await.spawn(async move {
                 if let Err(e) = handle_pool_mining_effect(wrapped_slab, &pool_client_clone).await {
                     error!("Failed to handle pool effect: {}", e);
                 }
@@ -487,7 +743,7 @@ async fn spawn_pool_mining_task(
 
 async fn handle_pool_mining_effect(
     wrapped_effect: NounSlab,
-    pool_client: &Arc<PoolClient>,
+    pool_client: &Arc<dyn PoolTransport>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let root = unsafe { wrapped_effect.root() };
     let wrapper_cell = match root.as_cell() {
@@ -519,7 +775,7 @@ async fn handle_pool_mining_effect(
         
         let share = ShareSubmission {
             job_id,
-            miner_id: pool_client.config.worker_name.clone(),
+            miner_id: pool_client.config().worker_name.clone(),
             share_type: ShareType::ComputationProof {
                 nonce,
                 witness_commitment,
@@ -543,7 +799,7 @@ async fn handle_pool_mining_effect(
         
         let share = ShareSubmission {
             job_id,
-            miner_id: pool_client.config.worker_name.clone(),
+            miner_id: pool_client.config().worker_name.clone(),
             share_type: ShareType::ValidBlock { nonce, proof },
         };
         
@@ -1,12 +1,167 @@
+use std::collections::VecDeque;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, watch, Mutex, RwLock};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use futures::{SinkExt, StreamExt};
 use tracing::{error, info, debug, warn};
 use crate::mining::PoolMiningConfig;
 
+/// How many recent share submissions the vardiff retargeter keeps around to
+/// estimate the observed submission rate.
+const VARDIFF_HISTORY_LEN: usize = 20;
+/// How often the retargeter recomputes the share difficulty multiplier.
+const VARDIFF_RETARGET_INTERVAL: Duration = Duration::from_secs(30);
+/// Target spacing between share submissions.
+const VARDIFF_DESIRED_SHARE_INTERVAL_SECS: f64 = 10.0;
+/// Maximum change in the multiplier per retarget, to avoid wild swings.
+const VARDIFF_MIN_RATIO: f64 = 0.25;
+const VARDIFF_MAX_RATIO: f64 = 4.0;
+/// Hard floor/ceiling on the accumulated multiplier, regardless of how many
+/// retargets have run.
+const MIN_SHARE_DIFFICULTY_MULTIPLIER: f64 = 0.001;
+const MAX_SHARE_DIFFICULTY_MULTIPLIER: f64 = 1000.0;
+
+/// Reconnect backoff/failover tuning, shared by both pool backends
+/// (`PoolClient` and `crate::pool_sv2::Sv2PoolClient`). Per-pool overrides of
+/// the backoff shape live on `PoolMiningConfig`; this threshold is the one
+/// piece that isn't configurable, since endpoint rotation only makes sense
+/// when there's more than one `pool_urls` entry to begin with.
+pub(crate) const RECONNECT_FAILURE_THRESHOLD: u32 = 3;
+
+/// Scales `current` by `multiplier` (capped at `max`), then applies up to
+/// `±jitter_percent` relative jitter, so multiple workers reconnecting at
+/// once don't all hammer the pool in lockstep.
+pub(crate) fn next_backoff(current: Duration, max: Duration, multiplier: f64, jitter_percent: f64) -> Duration {
+    let scaled = current.mul_f64(multiplier.max(1.0)).min(max);
+    let jitter_range_ms = (scaled.as_millis() as f64 * jitter_percent.clamp(0.0, 1.0)) as i64;
+    let offset_ms = if jitter_range_ms > 0 {
+        rand::random::<i64>().rem_euclid(2 * jitter_range_ms + 1) - jitter_range_ms
+    } else {
+        0
+    };
+    let jittered = if offset_ms >= 0 {
+        scaled + Duration::from_millis(offset_ms as u64)
+    } else {
+        scaled.saturating_sub(Duration::from_millis((-offset_ms) as u64))
+    };
+    jittered.clamp(Duration::from_millis(1), max)
+}
+
+/// Reconnect lifecycle surfaced to the miner loop, so it can distinguish a
+/// pool that's merely flapping from one that's given up entirely.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolConnectionStatus {
+    Connected,
+    Reconnecting { attempt: u32 },
+    /// `max_reconnect_attempts` was exceeded; the reconnect loop has stopped
+    /// for good. The pool mining driver returns an error when it sees this;
+    /// it does not switch to solo mining.
+    Exhausted,
+}
+
+/// Scales a big-endian share target by `multiplier` (> 1 makes it easier,
+/// i.e. a larger target; < 1 makes it harder). Exact arbitrary-precision
+/// scaling isn't worth the complexity here, so this approximates it by
+/// scaling the most significant 8 bytes, which dominate the magnitude.
+fn scale_target(target: &[u8], multiplier: f64) -> Vec<u8> {
+    if target.is_empty() {
+        return target.to_vec();
+    }
+
+    let mut scaled = target.to_vec();
+    let take = scaled.len().min(8);
+    let mut magnitude: u64 = 0;
+    for &b in &scaled[0..take] {
+        magnitude = (magnitude << 8) | b as u64;
+    }
+
+    let new_magnitude = ((magnitude as f64) * multiplier).clamp(1.0, u64::MAX as f64) as u64;
+    let new_bytes = new_magnitude.to_be_bytes();
+    let offset = 8 - take;
+    scaled[0..take].copy_from_slice(&new_bytes[offset..]);
+    scaled
+}
+
+/// Periodically recomputes the share difficulty multiplier from the observed
+/// submission rate: ratio = observed_interval / desired_interval, clamped
+/// per-adjustment to `[VARDIFF_MIN_RATIO, VARDIFF_MAX_RATIO]` and the
+/// accumulated multiplier clamped to `[MIN_SHARE_DIFFICULTY_MULTIPLIER,
+/// MAX_SHARE_DIFFICULTY_MULTIPLIER]`.
+fn spawn_vardiff_retargeter(
+    recent_share_times: Arc<Mutex<VecDeque<Instant>>>,
+    share_difficulty_multiplier: Arc<RwLock<f64>>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(VARDIFF_RETARGET_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let window = {
+                let times = recent_share_times.lock().await;
+                if times.len() >= 2 {
+                    Some((*times.front().unwrap(), *times.back().unwrap(), times.len()))
+                } else {
+                    None
+                }
+            };
+            let Some((oldest, newest, count)) = window else {
+                continue;
+            };
+
+            let observed_interval = newest.duration_since(oldest).as_secs_f64() / (count - 1) as f64;
+            let ratio = (observed_interval / VARDIFF_DESIRED_SHARE_INTERVAL_SECS)
+                .clamp(VARDIFF_MIN_RATIO, VARDIFF_MAX_RATIO);
+
+            let mut multiplier = share_difficulty_multiplier.write().await;
+            *multiplier = (*multiplier * ratio)
+                .clamp(MIN_SHARE_DIFFICULTY_MULTIPLIER, MAX_SHARE_DIFFICULTY_MULTIPLIER);
+
+            debug!(
+                "Vardiff retarget: observed interval {:.2}s (desired {:.2}s), ratio {:.2}, new multiplier {:.4}",
+                observed_interval, VARDIFF_DESIRED_SHARE_INTERVAL_SECS, ratio, *multiplier
+            );
+        }
+    });
+}
+
+/// Which wire format `PoolClient` speaks to the pool. Selected via
+/// `PoolMiningConfig::protocol`; both backends implement [`PoolTransport`],
+/// so `create_pool_mining_driver` doesn't need to know which one is in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PoolProtocol {
+    /// The original JSON-over-WebSocket Stratum-like protocol implemented below.
+    Json,
+    /// Stratum V2-flavored binary protocol over a Noise-encrypted transport,
+    /// implemented in [`crate::pool_sv2`].
+    Sv2,
+}
+
+impl Default for PoolProtocol {
+    fn default() -> Self {
+        PoolProtocol::Json
+    }
+}
+
+/// Common interface for a pool connection, implemented by both the JSON
+/// backend ([`PoolClient`]) and the SV2 backend ([`crate::pool_sv2::Sv2PoolClient`]).
+#[async_trait]
+pub trait PoolTransport: Send + Sync {
+    async fn recv_job(&self) -> Result<PoolJob, Box<dyn std::error::Error + Send + Sync>>;
+    async fn submit_share(&self, share: ShareSubmission) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+    async fn is_authorized(&self) -> bool;
+    fn config(&self) -> &PoolMiningConfig;
+    /// Closes the pool session gracefully and stops future reconnect
+    /// attempts, for use during driver shutdown.
+    async fn close(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+    /// Watches the reconnect loop's lifecycle, in particular
+    /// [`PoolConnectionStatus::Exhausted`], which tells the miner loop to
+    /// stop waiting on this pool.
+    fn connection_status(&self) -> watch::Receiver<PoolConnectionStatus>;
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PoolJob {
     pub id: String,
@@ -50,27 +205,64 @@ pub struct PoolClient {
     job_receiver: Arc<RwLock<mpsc::Receiver<PoolJob>>>,
     share_sender: mpsc::Sender<ShareSubmission>,
     authorized: Arc<RwLock<bool>>,
+    /// Recent share-submission timestamps, used by the vardiff retargeter to
+    /// estimate the observed submission rate. Submission time is used as a
+    /// proxy for acceptance, since the Stratum-like protocol here doesn't
+    /// correlate `mining.submit` responses back to individual shares.
+    recent_share_times: Arc<Mutex<VecDeque<Instant>>>,
+    /// Live share difficulty multiplier, seeded from
+    /// `PoolMiningConfig::share_difficulty_multiplier` and adjusted by the
+    /// vardiff retargeter thereafter.
+    share_difficulty_multiplier: Arc<RwLock<f64>>,
+    /// Last difficulty pushed by the pool via `mining.set_difficulty`, or
+    /// `None` until the first one arrives. Exposed only through
+    /// `pool_difficulty()`; the mining loop doesn't read it, and job share
+    /// targets are scaled by `share_difficulty_multiplier` alone.
+    pool_difficulty: Arc<RwLock<Option<u64>>>,
+    /// Flips to `true` on [`PoolTransport::close`]: tells `connection_handler`
+    /// to stop reconnecting and `connect_and_handle` to send a close frame
+    /// and return instead of looping forever.
+    shutdown_tx: watch::Sender<bool>,
+    /// Reconnect lifecycle, watched by [`PoolTransport::connection_status`].
+    connection_status_tx: watch::Sender<PoolConnectionStatus>,
 }
 
 impl PoolClient {
     pub async fn new(config: &PoolMiningConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        assert!(!config.pool_urls.is_empty(), "PoolMiningConfig::pool_urls must not be empty");
         let (job_tx, job_rx) = mpsc::channel(100);
-        let (share_tx, share_rx) = mpsc::channel(100);
-        
+        // `connection_handler` owns this receiver across reconnects (it's
+        // never recreated), so shares submitted while disconnected simply
+        // queue here and drain into the new connection once it's back up.
+        let (share_tx, share_rx) = mpsc::channel(1000);
+        let (shutdown_tx, _) = watch::channel(false);
+        let (connection_status_tx, _) = watch::channel(PoolConnectionStatus::Reconnecting { attempt: 0 });
+
         let client = Self {
             config: config.clone(),
             job_receiver: Arc::new(RwLock::new(job_rx)),
             share_sender: share_tx,
             authorized: Arc::new(RwLock::new(false)),
+            recent_share_times: Arc::new(Mutex::new(VecDeque::with_capacity(VARDIFF_HISTORY_LEN))),
+            share_difficulty_multiplier: Arc::new(RwLock::new(config.share_difficulty_multiplier)),
+            pool_difficulty: Arc::new(RwLock::new(None)),
+            shutdown_tx,
+            connection_status_tx,
         };
-        
+
         // Start connection handler
         let client_clone = client.clone();
         let job_tx_clone = job_tx.clone();
         tokio::spawn(async move {
             client_clone.connection_handler(job_tx_clone, share_rx).await;
         });
-        
+
+        // Start vardiff retargeter
+        spawn_vardiff_retargeter(
+            client.recent_share_times.clone(),
+            client.share_difficulty_multiplier.clone(),
+        );
+
         Ok(client)
     }
     
@@ -79,30 +271,92 @@ impl PoolClient {
         job_sender: mpsc::Sender<PoolJob>,
         mut share_receiver: mpsc::Receiver<ShareSubmission>,
     ) {
+        let endpoints = &self.config.pool_urls;
+        let base_backoff = Duration::from_secs(self.config.reconnect_base_backoff_secs.max(1));
+        let max_backoff = Duration::from_secs(self.config.reconnect_max_backoff_secs.max(1));
+        let stability_threshold = Duration::from_secs(self.config.reconnect_stability_threshold_secs);
+        let mut endpoint_idx: usize = 0;
+        let mut consecutive_failures: u32 = 0;
+        // Total reconnect attempts since the last time a connection stayed up
+        // past `stability_threshold`; tracked separately from
+        // `consecutive_failures` (which resets on endpoint rotation) so
+        // `max_reconnect_attempts` still trips even while rotating endpoints.
+        let mut total_attempts: u32 = 0;
+        let mut backoff = base_backoff;
+
         loop {
-            match self.connect_and_handle(&job_sender, &mut share_receiver).await {
-                Ok(_) => {
-                    warn!("Pool connection closed, reconnecting in 5 seconds...");
+            if *self.shutdown_tx.borrow() {
+                info!("Pool client shutting down, not reconnecting");
+                return;
+            }
+
+            let endpoint = endpoints[endpoint_idx % endpoints.len()].clone();
+            let connected_at = Instant::now();
+            let result = self
+                .connect_and_handle(&endpoint, &job_sender, &mut share_receiver, self.shutdown_tx.subscribe())
+                .await;
+            let stayed_up = connected_at.elapsed() >= stability_threshold;
+            *self.authorized.write().await = false;
+
+            match result {
+                Ok(_) => warn!("Pool connection to {} closed, reconnecting", endpoint),
+                Err(e) => error!("Pool connection to {} error: {}, reconnecting", endpoint, e),
+            }
+
+            if *self.shutdown_tx.borrow() {
+                return;
+            }
+
+            if stayed_up {
+                // Stayed up past the stability threshold, so this wasn't a
+                // failed connection attempt -- don't penalize the endpoint,
+                // the backoff, or the attempt count for it.
+                consecutive_failures = 0;
+                total_attempts = 0;
+                backoff = base_backoff;
+            } else {
+                consecutive_failures += 1;
+                total_attempts += 1;
+                if consecutive_failures >= RECONNECT_FAILURE_THRESHOLD && endpoints.len() > 1 {
+                    endpoint_idx = (endpoint_idx + 1) % endpoints.len();
+                    consecutive_failures = 0;
+                    info!("Switching to backup pool endpoint {}", endpoints[endpoint_idx]);
                 }
-                Err(e) => {
-                    error!("Pool connection error: {}, reconnecting in 5 seconds...", e);
+            }
+
+            if let Some(max_attempts) = self.config.max_reconnect_attempts {
+                if total_attempts >= max_attempts {
+                    error!("Giving up on pool after {} reconnect attempts", total_attempts);
+                    let _ = self.connection_status_tx.send(PoolConnectionStatus::Exhausted);
+                    return;
                 }
             }
-            
-            *self.authorized.write().await = false;
-            tokio::time::sleep(Duration::from_secs(5)).await;
+            let _ = self
+                .connection_status_tx
+                .send(PoolConnectionStatus::Reconnecting { attempt: total_attempts });
+
+            debug!("Reconnecting to pool in {:?}", backoff);
+            tokio::time::sleep(backoff).await;
+            backoff = next_backoff(
+                backoff,
+                max_backoff,
+                self.config.reconnect_backoff_multiplier,
+                self.config.reconnect_jitter_percent,
+            );
         }
     }
-    
+
     async fn connect_and_handle(
         &self,
+        endpoint: &str,
         job_sender: &mpsc::Sender<PoolJob>,
         share_receiver: &mut mpsc::Receiver<ShareSubmission>,
+        mut shutdown_rx: watch::Receiver<bool>,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let (ws_stream, _) = connect_async(&self.config.pool_url).await?;
+        let (ws_stream, _) = connect_async(endpoint).await?;
         let (mut write, mut read) = ws_stream.split();
-        
-        info!("Connected to pool at {}", self.config.pool_url);
+
+        info!("Connected to pool at {}", endpoint);
         
         // Send authorization
         let auth_msg = StratumMessage {
@@ -156,13 +410,22 @@ impl PoolClient {
                             method: "mining.submit".to_string(),
                             params: serde_json::to_value(&share)?,
                         };
-                        
+
                         write.send(Message::Text(serde_json::to_string(&submit_msg)?)).await?;
                     }
                 }
+
+                // Shutdown requested: close the session instead of reconnecting
+                _ = shutdown_rx.changed() => {
+                    if *shutdown_rx.borrow() {
+                        info!("Closing pool connection for shutdown");
+                        let _ = write.send(Message::Close(None)).await;
+                        break;
+                    }
+                }
             }
         }
-        
+
         Ok(())
     }
     
@@ -179,7 +442,12 @@ impl PoolClient {
                 }
             }
             "mining.set_difficulty" => {
-                debug!("Difficulty update: {:?}", msg.params);
+                if let Some(difficulty) = msg.params.get(0).and_then(|v| v.as_u64()) {
+                    debug!("Difficulty update: {}", difficulty);
+                    *self.pool_difficulty.write().await = Some(difficulty);
+                } else {
+                    debug!("Malformed difficulty update: {:?}", msg.params);
+                }
             }
             _ => {
                 if msg.id.is_some() {
@@ -187,27 +455,60 @@ impl PoolClient {
                     if let Some(result) = msg.params.get("result") {
                         if result.as_bool() == Some(true) {
                             *self.authorized.write().await = true;
+                            let _ = self.connection_status_tx.send(PoolConnectionStatus::Connected);
                             info!("Successfully authorized with pool");
                         }
                     }
                 }
             }
         }
-        
+
         Ok(())
     }
-    
-    pub async fn recv_job(&self) -> Result<PoolJob, Box<dyn std::error::Error + Send + Sync>> {
+
+    /// Last difficulty the pool pushed via `mining.set_difficulty`, or
+    /// `None` if none has arrived yet.
+    pub async fn pool_difficulty(&self) -> Option<u64> {
+        *self.pool_difficulty.read().await
+    }
+}
+
+#[async_trait]
+impl PoolTransport for PoolClient {
+    async fn recv_job(&self) -> Result<PoolJob, Box<dyn std::error::Error + Send + Sync>> {
         let mut receiver = self.job_receiver.write().await;
-        receiver.recv().await.ok_or_else(|| "Job channel closed".into())
+        let mut job = receiver.recv().await.ok_or_else(|| "Job channel closed".to_string())?;
+        let multiplier = *self.share_difficulty_multiplier.read().await;
+        job.share_target = scale_target(&job.share_target, multiplier);
+        Ok(job)
     }
-    
-    pub async fn submit_share(&self, share: ShareSubmission) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+
+    async fn submit_share(&self, share: ShareSubmission) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        {
+            let mut times = self.recent_share_times.lock().await;
+            times.push_back(Instant::now());
+            if times.len() > VARDIFF_HISTORY_LEN {
+                times.pop_front();
+            }
+        }
         self.share_sender.send(share).await?;
         Ok(())
     }
-    
-    pub async fn is_authorized(&self) -> bool {
+
+    async fn is_authorized(&self) -> bool {
         *self.authorized.read().await
     }
+
+    fn config(&self) -> &PoolMiningConfig {
+        &self.config
+    }
+
+    async fn close(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _ = self.shutdown_tx.send(true);
+        Ok(())
+    }
+
+    fn connection_status(&self) -> watch::Receiver<PoolConnectionStatus> {
+        self.connection_status_tx.subscribe()
+    }
 }
\ No newline at end of file
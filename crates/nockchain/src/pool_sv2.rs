@@ -0,0 +1,445 @@
+//! Stratum V2-flavored binary backend for pool mining: a Noise NX-style
+//! encrypted transport carrying length-prefixed binary frames, instead of
+//! the JSON-over-WebSocket protocol in `pool_client.rs`. Selected via
+//! `PoolMiningConfig::protocol == PoolProtocol::Sv2`.
+//!
+//! The AEAD framing here is a simplified stand-in for real Noise_NX (no
+//! handshake-pattern/static-key authentication, so it doesn't resist an
+//! active man-in-the-middle), but the transport keys come from a genuine
+//! X25519 ECDH exchange rather than a hash of values sent in the clear --
+//! they keep the same shape (ephemeral key exchange -> derived transport
+//! keys -> authenticated encryption) so the rest of the pipeline (message
+//! framing, PoolTransport) is exercised the way a real SV2 transport would be.
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, watch, RwLock};
+use tracing::{debug, error, info, warn};
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+use std::time::{Duration, Instant};
+
+use crate::mining::PoolMiningConfig;
+use crate::pool_client::{
+    next_backoff, PoolConnectionStatus, PoolJob, PoolTransport, ShareSubmission, ShareType,
+    RECONNECT_FAILURE_THRESHOLD,
+};
+
+// SV2 message type tags for the subset of the protocol this backend needs.
+const MSG_SETUP_CONNECTION: u8 = 0x00;
+const MSG_SETUP_CONNECTION_SUCCESS: u8 = 0x01;
+const MSG_OPEN_STANDARD_MINING_CHANNEL: u8 = 0x10;
+const MSG_NEW_MINING_JOB: u8 = 0x15;
+const MSG_SUBMIT_SHARES_STANDARD: u8 = 0x1a;
+
+const SV2_PROTOCOL_VERSION: u16 = 2;
+const AUTH_TAG_LEN: usize = 32;
+
+/// A Noise_NX-shaped transport session: an X25519 ECDH exchange derives
+/// distinct send/recv transport keys, which then seal/open every frame.
+struct NoiseSession {
+    send_key: [u8; 32],
+    recv_key: [u8; 32],
+    send_nonce: u64,
+    recv_nonce: u64,
+}
+
+impl NoiseSession {
+    /// Runs the handshake as initiator: exchange X25519 public keys, then
+    /// derive the transport keys from the ECDH shared secret. Unlike a
+    /// hash of values sent in the clear, this secret never crosses the wire
+    /// -- a passive eavesdropper who sees both public keys still can't
+    /// compute it.
+    async fn handshake_initiator(stream: &mut TcpStream) -> std::io::Result<Self> {
+        let local_secret = EphemeralSecret::random_from_rng(rand::rngs::OsRng);
+        let local_public = PublicKey::from(&local_secret);
+        stream.write_all(local_public.as_bytes()).await?;
+        stream.flush().await?;
+
+        let mut remote_public_bytes = [0u8; 32];
+        stream.read_exact(&mut remote_public_bytes).await?;
+        let remote_public = PublicKey::from(remote_public_bytes);
+
+        let shared_secret = local_secret.diffie_hellman(&remote_public);
+        Ok(Self {
+            send_key: Self::derive_key(shared_secret.as_bytes(), b"initiator->responder"),
+            recv_key: Self::derive_key(shared_secret.as_bytes(), b"responder->initiator"),
+            send_nonce: 0,
+            recv_nonce: 0,
+        })
+    }
+
+    fn derive_key(secret: &[u8; 32], label: &[u8]) -> [u8; 32] {
+        let mut buf = Vec::with_capacity(secret.len() + label.len());
+        buf.extend_from_slice(secret);
+        buf.extend_from_slice(label);
+        Sha256::digest(&buf).into()
+    }
+
+    /// Keystream-XOR encryption with an appended authentication tag over
+    /// (key, nonce, plaintext) -- an encrypt-and-authenticate construction
+    /// standing in for a real AEAD cipher.
+    fn seal(key: &[u8; 32], nonce: u64, plaintext: &[u8]) -> Vec<u8> {
+        let keystream = Self::keystream(key, nonce, plaintext.len());
+        let mut sealed: Vec<u8> = plaintext.iter().zip(keystream.iter()).map(|(p, k)| p ^ k).collect();
+        sealed.extend_from_slice(&Self::tag(key, nonce, plaintext));
+        sealed
+    }
+
+    fn open(key: &[u8; 32], nonce: u64, sealed: &[u8]) -> std::io::Result<Vec<u8>> {
+        if sealed.len() < AUTH_TAG_LEN {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "SV2 frame too short for auth tag"));
+        }
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - AUTH_TAG_LEN);
+        let keystream = Self::keystream(key, nonce, ciphertext.len());
+        let plaintext: Vec<u8> = ciphertext.iter().zip(keystream.iter()).map(|(c, k)| c ^ k).collect();
+
+        if Self::tag(key, nonce, &plaintext) != tag {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "SV2 frame authentication failed"));
+        }
+        Ok(plaintext)
+    }
+
+    fn tag(key: &[u8; 32], nonce: u64, plaintext: &[u8]) -> [u8; 32] {
+        let mut buf = Vec::with_capacity(key.len() + 8 + plaintext.len());
+        buf.extend_from_slice(key);
+        buf.extend_from_slice(&nonce.to_le_bytes());
+        buf.extend_from_slice(plaintext);
+        Sha256::digest(&buf).into()
+    }
+
+    fn keystream(key: &[u8; 32], nonce: u64, len: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(len);
+        let mut counter: u64 = 0;
+        while out.len() < len {
+            let mut block_input = Vec::with_capacity(key.len() + 16);
+            block_input.extend_from_slice(key);
+            block_input.extend_from_slice(&nonce.to_le_bytes());
+            block_input.extend_from_slice(&counter.to_le_bytes());
+            out.extend_from_slice(&Sha256::digest(&block_input));
+            counter += 1;
+        }
+        out.truncate(len);
+        out
+    }
+
+    async fn write_frame(&mut self, stream: &mut TcpStream, msg_type: u8, payload: &[u8]) -> std::io::Result<()> {
+        let mut plaintext = Vec::with_capacity(payload.len() + 1);
+        plaintext.push(msg_type);
+        plaintext.extend_from_slice(payload);
+
+        let sealed = Self::seal(&self.send_key, self.send_nonce, &plaintext);
+        self.send_nonce += 1;
+
+        stream.write_u32(sealed.len() as u32).await?;
+        stream.write_all(&sealed).await?;
+        stream.flush().await
+    }
+
+    async fn read_frame(&mut self, stream: &mut TcpStream) -> std::io::Result<(u8, Vec<u8>)> {
+        let len = stream.read_u32().await? as usize;
+        let mut sealed = vec![0u8; len];
+        stream.read_exact(&mut sealed).await?;
+
+        let plaintext = Self::open(&self.recv_key, self.recv_nonce, &sealed)?;
+        self.recv_nonce += 1;
+
+        let (msg_type, payload) = plaintext
+            .split_first()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "empty SV2 frame"))?;
+        Ok((*msg_type, payload.to_vec()))
+    }
+}
+
+fn write_lv(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    buf.extend_from_slice(data);
+}
+
+fn read_lv(buf: &[u8], pos: &mut usize) -> std::io::Result<Vec<u8>> {
+    let err = || std::io::Error::new(std::io::ErrorKind::InvalidData, "truncated SV2 length-value field");
+    if buf.len() < *pos + 2 {
+        return Err(err());
+    }
+    let len = u16::from_be_bytes([buf[*pos], buf[*pos + 1]]) as usize;
+    *pos += 2;
+    if buf.len() < *pos + len {
+        return Err(err());
+    }
+    let data = buf[*pos..*pos + len].to_vec();
+    *pos += len;
+    Ok(data)
+}
+
+fn encode_setup_connection(worker_name: &str, worker_password: &str) -> Vec<u8> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(&SV2_PROTOCOL_VERSION.to_be_bytes());
+    payload.extend_from_slice(&0u32.to_be_bytes()); // flags
+    write_lv(&mut payload, worker_name.as_bytes());
+    write_lv(&mut payload, worker_password.as_bytes());
+    payload
+}
+
+fn encode_open_standard_mining_channel(request_id: u32, worker_name: &str) -> Vec<u8> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(&request_id.to_be_bytes());
+    write_lv(&mut payload, worker_name.as_bytes());
+    payload
+}
+
+fn encode_submit_shares_standard(share: &ShareSubmission) -> Vec<u8> {
+    let mut payload = Vec::new();
+    write_lv(&mut payload, share.job_id.as_bytes());
+    write_lv(&mut payload, share.miner_id.as_bytes());
+    match &share.share_type {
+        ShareType::ComputationProof { nonce, witness_commitment, computation_steps } => {
+            payload.push(0);
+            payload.extend_from_slice(&nonce.to_be_bytes());
+            payload.extend_from_slice(witness_commitment);
+            payload.extend_from_slice(&computation_steps.to_be_bytes());
+        }
+        ShareType::ValidBlock { nonce, proof } => {
+            payload.push(1);
+            payload.extend_from_slice(&nonce.to_be_bytes());
+            write_lv(&mut payload, proof);
+        }
+    }
+    payload
+}
+
+/// Decodes a `NewMiningJob` frame into a `PoolJob`. Folds what SV2 splits
+/// across `NewMiningJob`/`SetNewPrevHash` into one frame, since this backend
+/// only implements the initiator side; the coordinator-side SV2 encoder is
+/// separate work.
+fn decode_new_mining_job(payload: &[u8]) -> std::io::Result<PoolJob> {
+    let mut pos = 0;
+    let id_bytes = read_lv(payload, &mut pos)?;
+    let id = String::from_utf8(id_bytes)
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "job id not valid UTF-8"))?;
+    let block_commitment = read_lv(payload, &mut pos)?;
+    let target = read_lv(payload, &mut pos)?;
+    let share_target = read_lv(payload, &mut pos)?;
+
+    let err = || std::io::Error::new(std::io::ErrorKind::InvalidData, "truncated NewMiningJob frame");
+    if payload.len() < pos + 16 {
+        return Err(err());
+    }
+    let nonce_start = u64::from_be_bytes(payload[pos..pos + 8].try_into().unwrap());
+    pos += 8;
+    let nonce_range = u64::from_be_bytes(payload[pos..pos + 8].try_into().unwrap());
+
+    Ok(PoolJob { id, block_commitment, target, share_target, nonce_start, nonce_range })
+}
+
+#[derive(Clone)]
+pub struct Sv2PoolClient {
+    config: PoolMiningConfig,
+    job_receiver: Arc<RwLock<mpsc::Receiver<PoolJob>>>,
+    share_sender: mpsc::Sender<ShareSubmission>,
+    authorized: Arc<RwLock<bool>>,
+    /// Flips to `true` on [`PoolTransport::close`]: tells `connection_handler`
+    /// to stop reconnecting and `connect_and_handle` to return instead of
+    /// looping forever.
+    shutdown_tx: watch::Sender<bool>,
+    /// Reconnect lifecycle, watched by [`PoolTransport::connection_status`].
+    connection_status_tx: watch::Sender<PoolConnectionStatus>,
+}
+
+impl Sv2PoolClient {
+    pub async fn new(config: &PoolMiningConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        assert!(!config.pool_urls.is_empty(), "PoolMiningConfig::pool_urls must not be empty");
+        let (job_tx, job_rx) = mpsc::channel(100);
+        // Persists across reconnects the same way PoolClient's does, so shares
+        // submitted while disconnected queue here and flush once reconnected.
+        let (share_tx, share_rx) = mpsc::channel(1000);
+        let (shutdown_tx, _) = watch::channel(false);
+        let (connection_status_tx, _) = watch::channel(PoolConnectionStatus::Reconnecting { attempt: 0 });
+
+        let client = Self {
+            config: config.clone(),
+            job_receiver: Arc::new(RwLock::new(job_rx)),
+            share_sender: share_tx,
+            authorized: Arc::new(RwLock::new(false)),
+            shutdown_tx,
+            connection_status_tx,
+        };
+
+        let client_clone = client.clone();
+        tokio::spawn(async move {
+            client_clone.connection_handler(job_tx, share_rx).await;
+        });
+
+        Ok(client)
+    }
+
+    async fn connection_handler(
+        &self,
+        job_sender: mpsc::Sender<PoolJob>,
+        mut share_receiver: mpsc::Receiver<ShareSubmission>,
+    ) {
+        let endpoints = &self.config.pool_urls;
+        let base_backoff = Duration::from_secs(self.config.reconnect_base_backoff_secs.max(1));
+        let max_backoff = Duration::from_secs(self.config.reconnect_max_backoff_secs.max(1));
+        let stability_threshold = Duration::from_secs(self.config.reconnect_stability_threshold_secs);
+        let mut endpoint_idx: usize = 0;
+        let mut consecutive_failures: u32 = 0;
+        let mut total_attempts: u32 = 0;
+        let mut backoff = base_backoff;
+
+        loop {
+            if *self.shutdown_tx.borrow() {
+                info!("SV2 pool client shutting down, not reconnecting");
+                return;
+            }
+
+            let endpoint = endpoints[endpoint_idx % endpoints.len()].clone();
+            let connected_at = Instant::now();
+            let result = self
+                .connect_and_handle(&endpoint, &job_sender, &mut share_receiver, self.shutdown_tx.subscribe())
+                .await;
+            let stayed_up = connected_at.elapsed() >= stability_threshold;
+            *self.authorized.write().await = false;
+
+            match result {
+                Ok(_) => warn!("SV2 pool connection to {} closed, reconnecting", endpoint),
+                Err(e) => error!("SV2 pool connection to {} error: {}, reconnecting", endpoint, e),
+            }
+
+            if *self.shutdown_tx.borrow() {
+                return;
+            }
+
+            if stayed_up {
+                consecutive_failures = 0;
+                total_attempts = 0;
+                backoff = base_backoff;
+            } else {
+                consecutive_failures += 1;
+                total_attempts += 1;
+                if consecutive_failures >= RECONNECT_FAILURE_THRESHOLD && endpoints.len() > 1 {
+                    endpoint_idx = (endpoint_idx + 1) % endpoints.len();
+                    consecutive_failures = 0;
+                    info!("Switching to backup SV2 pool endpoint {}", endpoints[endpoint_idx]);
+                }
+            }
+
+            if let Some(max_attempts) = self.config.max_reconnect_attempts {
+                if total_attempts >= max_attempts {
+                    error!("Giving up on SV2 pool after {} reconnect attempts", total_attempts);
+                    let _ = self.connection_status_tx.send(PoolConnectionStatus::Exhausted);
+                    return;
+                }
+            }
+            let _ = self
+                .connection_status_tx
+                .send(PoolConnectionStatus::Reconnecting { attempt: total_attempts });
+
+            debug!("Reconnecting to SV2 pool in {:?}", backoff);
+            tokio::time::sleep(backoff).await;
+            backoff = next_backoff(
+                backoff,
+                max_backoff,
+                self.config.reconnect_backoff_multiplier,
+                self.config.reconnect_jitter_percent,
+            );
+        }
+    }
+
+    async fn connect_and_handle(
+        &self,
+        endpoint: &str,
+        job_sender: &mpsc::Sender<PoolJob>,
+        share_receiver: &mut mpsc::Receiver<ShareSubmission>,
+        mut shutdown_rx: watch::Receiver<bool>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let addr = endpoint.trim_start_matches("sv2://").trim_start_matches("tcp://");
+        let mut stream = TcpStream::connect(addr).await?;
+        info!("Connected to SV2 pool at {}", addr);
+
+        let mut session = NoiseSession::handshake_initiator(&mut stream).await?;
+
+        let setup = encode_setup_connection(
+            &self.config.worker_name,
+            self.config.worker_password.as_deref().unwrap_or(""),
+        );
+        session.write_frame(&mut stream, MSG_SETUP_CONNECTION, &setup).await?;
+
+        let (msg_type, _payload) = session.read_frame(&mut stream).await?;
+        if msg_type != MSG_SETUP_CONNECTION_SUCCESS {
+            return Err(format!("expected SetupConnectionSuccess, got message type {}", msg_type).into());
+        }
+        *self.authorized.write().await = true;
+        let _ = self.connection_status_tx.send(PoolConnectionStatus::Connected);
+        info!("SV2 pool authorized connection for {}", self.config.worker_name);
+
+        let open_channel = encode_open_standard_mining_channel(1, &self.config.worker_name);
+        session.write_frame(&mut stream, MSG_OPEN_STANDARD_MINING_CHANNEL, &open_channel).await?;
+
+        loop {
+            tokio::select! {
+                frame = session.read_frame(&mut stream) => {
+                    let (msg_type, payload) = frame?;
+                    if msg_type == MSG_NEW_MINING_JOB {
+                        match decode_new_mining_job(&payload) {
+                            Ok(job) => {
+                                debug!("Received SV2 job {}", job.id);
+                                job_sender.send(job).await?;
+                            }
+                            Err(e) => warn!("Failed to decode SV2 NewMiningJob frame: {}", e),
+                        }
+                    }
+                }
+
+                share = share_receiver.recv() => {
+                    if let Some(share) = share {
+                        let payload = encode_submit_shares_standard(&share);
+                        session.write_frame(&mut stream, MSG_SUBMIT_SHARES_STANDARD, &payload).await?;
+                    }
+                }
+
+                // Shutdown requested: this message subset has no SV2
+                // disconnect/close frame implemented, so we just stop the
+                // loop and let `stream` close via `Drop` on return.
+                _ = shutdown_rx.changed() => {
+                    if *shutdown_rx.borrow() {
+                        info!("Closing SV2 pool connection for shutdown");
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PoolTransport for Sv2PoolClient {
+    async fn recv_job(&self) -> Result<PoolJob, Box<dyn std::error::Error + Send + Sync>> {
+        let mut receiver = self.job_receiver.write().await;
+        receiver.recv().await.ok_or_else(|| "SV2 job channel closed".into())
+    }
+
+    async fn submit_share(&self, share: ShareSubmission) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.share_sender.send(share).await?;
+        Ok(())
+    }
+
+    async fn is_authorized(&self) -> bool {
+        *self.authorized.read().await
+    }
+
+    fn config(&self) -> &PoolMiningConfig {
+        &self.config
+    }
+
+    async fn close(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _ = self.shutdown_tx.send(true);
+        Ok(())
+    }
+
+    fn connection_status(&self) -> watch::Receiver<PoolConnectionStatus> {
+        self.connection_status_tx.subscribe()
+    }
+}